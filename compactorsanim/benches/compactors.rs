@@ -0,0 +1,111 @@
+use compactorsanim::{compactors::Compactors, frame::NoFrames};
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use rand::{prelude::StdRng, Rng, SeedableRng};
+
+const KS: [usize; 3] = [10, 100, 1000];
+const LENGTHS: [usize; 2] = [1_000, 100_000];
+
+fn stream(length: usize) -> Vec<u32> {
+    let mut r = StdRng::seed_from_u64(42);
+    (0..length).map(|_| r.gen()).collect()
+}
+
+fn fill<const LAZY: bool>(k: usize, items: &[u32]) -> Compactors<u32, NoFrames, LAZY> {
+    let mut compactors = Compactors::new(k, NoFrames);
+    for &item in items {
+        compactors.update(item);
+    }
+    compactors
+}
+
+// Whole streams into a fresh sketch, including every compaction along the way.
+fn update<const LAZY: bool>(c: &mut Criterion, name: &str) {
+    let mut group = c.benchmark_group(name);
+    for length in LENGTHS {
+        let items = stream(length);
+        group.throughput(Throughput::Elements(length as u64));
+        for k in KS {
+            group.bench_with_input(
+                BenchmarkId::new(format!("k={}", k), length),
+                &items,
+                |b, items| b.iter(|| fill::<LAZY>(k, items)),
+            );
+        }
+    }
+    group.finish();
+}
+
+fn update_lazy(c: &mut Criterion) {
+    update::<true>(c, "update/lazy");
+}
+
+fn update_eager(c: &mut Criterion) {
+    update::<false>(c, "update/eager");
+}
+
+// The same streams as update/lazy, added in one batch.
+fn extend(c: &mut Criterion) {
+    let mut group = c.benchmark_group("extend");
+    for length in LENGTHS {
+        let items = stream(length);
+        group.throughput(Throughput::Elements(length as u64));
+        for k in KS {
+            group.bench_with_input(
+                BenchmarkId::new(format!("k={}", k), length),
+                &items,
+                |b, items| {
+                    b.iter(|| {
+                        let mut compactors: Compactors<u32, _, true> = Compactors::new(k, NoFrames);
+                        compactors.extend(items.iter().copied());
+                        compactors
+                    })
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+// Large levels, where the cost of keeping them in order dominates.
+fn update_large_k(c: &mut Criterion) {
+    let mut group = c.benchmark_group("update/large-k");
+    group.sample_size(10);
+    let length = 1_000_000;
+    let items = stream(length);
+    group.throughput(Throughput::Elements(length as u64));
+    for k in [10_000, 100_000] {
+        group.bench_with_input(BenchmarkId::from_parameter(k), &items, |b, items| {
+            b.iter(|| fill::<true>(k, items))
+        });
+    }
+    group.finish();
+}
+
+// The single update that fills level 0 of a sketch holding k items, which sorts and compacts it.
+fn compaction(c: &mut Criterion) {
+    let mut group = c.benchmark_group("compaction");
+    for k in KS {
+        let items = stream(k + 1);
+        group.bench_with_input(BenchmarkId::from_parameter(k), &items, |b, items| {
+            b.iter_batched(
+                || fill::<true>(k, &items[..k]),
+                |mut compactors| {
+                    compactors.update(items[k]);
+                    compactors
+                },
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    update_lazy,
+    update_eager,
+    extend,
+    update_large_k,
+    compaction
+);
+criterion_main!(benches);
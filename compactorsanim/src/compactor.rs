@@ -2,12 +2,15 @@
 pub struct Compactor<T: Ord> {
     pub data: Vec<T>,
     pub capacity: usize,
+    // How many times this level has been compacted into the next one.
+    pub compactions: usize,
 }
 impl<T: Ord> Compactor<T> {
     pub fn new(capacity: usize) -> Self {
         Self {
             data: Default::default(),
             capacity,
+            compactions: 0,
         }
     }
 }
@@ -1,91 +1,280 @@
-use crate::compactor::Compactor;
-use std::{cell::RefCell, cmp::max};
+use crate::{
+    compactor::Compactor,
+    frame::{Frame, FrameEvent, FrameHandler},
+    stats::Stats,
+    tracked::Tracked,
+};
+use std::{cmp::max, iter::FromIterator};
 
-const CAPACITY_COEFFICIENT: f64 = 0.7;
+pub const CAPACITY_COEFFICIENT: f64 = 0.7;
+// The k of sketches collected from an iterator.
+pub const DEFAULT_K: usize = 200;
 
 #[derive(Debug)]
 pub struct Compactors<T, F, const LAZY: bool>
 where
     T: Ord,
-    F: FnMut(Vec<Compactor<T>>),
+    F: FrameHandler<T>,
 {
     k: usize,
-    compactors: Vec<RefCell<Compactor<T>>>,
+    // How much smaller each level is than the one above it.
+    capacity_coefficient: f64,
+    compactors: Vec<Compactor<T>>,
     num_compactions: u32,
+    items_seen: usize,
+    // Called on every element that is promoted to the next level, used for identity tracking.
+    on_promote: Option<fn(&mut T, usize)>,
+    // Called on every element of a sketch merged into this one, with the number of items this one
+    // had seen, used to keep tracked ids unique.
+    on_merge: Option<fn(&mut T, usize)>,
+    // Whether every update records an Inserted frame, rather than only the compactions.
+    item_frames: bool,
     frame_handler: F,
 }
 
-impl<T, F, const LAZY: bool> Into<Vec<Compactor<T>>> for Compactors<T, F, LAZY>
+impl<T, F, const LAZY: bool> From<Compactors<T, F, LAZY>> for Vec<Compactor<T>>
 where
     T: Ord,
-    F: FnMut(Vec<Compactor<T>>),
+    F: FrameHandler<T>,
 {
-    fn into(self) -> Vec<Compactor<T>> {
-        self.compactors
-            .into_iter()
-            .map(|compactor| compactor.into_inner())
-            .collect()
+    fn from(compactors: Compactors<T, F, LAZY>) -> Self {
+        compactors.compactors
     }
 }
 
-fn capacity(k: usize, num_compactors: usize, level: usize) -> usize {
-    ((CAPACITY_COEFFICIENT.powf((num_compactors - level - 1) as f64) * k as f64).ceil() as u32 + 1)
-        as usize
+// Merges a sorted run into a sorted level. Equal items keep the level's first, just as sorting
+// the run appended to the level would.
+fn merge_sorted<T: Ord>(level: &mut Vec<T>, mut run: Vec<T>) {
+    match (level.last(), run.first()) {
+        (_, None) => return,
+        (None, _) => {
+            *level = run;
+            return;
+        }
+        (Some(last), Some(first)) if last <= first => {
+            level.append(&mut run);
+            return;
+        }
+        _ => {}
+    }
+    let existing = std::mem::take(level);
+    level.reserve(existing.len() + run.len());
+    let mut existing = existing.into_iter().peekable();
+    let mut run = run.into_iter().peekable();
+    loop {
+        let next = match (existing.peek(), run.peek()) {
+            (Some(a), Some(b)) if b < a => run.next(),
+            (Some(_), _) => existing.next(),
+            (None, Some(_)) => run.next(),
+            (None, None) => break,
+        };
+        level.extend(next);
+    }
+}
+
+fn capacity(k: usize, coefficient: f64, num_compactors: usize, level: usize) -> usize {
+    ((coefficient.powf((num_compactors - level - 1) as f64) * k as f64).ceil() as u32 + 1) as usize
+}
+
+impl<T, F, const LAZY: bool> Compactors<T, F, LAZY>
+where
+    T: Ord,
+    F: FrameHandler<T>,
+{
+    // Level h holds items of weight 2^h.
+    pub fn levels(&self) -> &[Compactor<T>] {
+        &self.compactors
+    }
+
+    // Every compaction at level h can shift the rank of any item by at most 2^h, the weight of
+    // the items discarded from that level.
+    pub fn rank_error_bound(&self) -> usize {
+        self.compactors
+            .iter()
+            .enumerate()
+            .map(|(level, c)| c.compactions << level)
+            .sum()
+    }
+
+    pub fn stats(&self) -> Stats {
+        Stats::new(&self.compactors, self.items_seen)
+    }
+
+    fn size(&self) -> usize {
+        self.compactors.iter().map(|c| c.data.len()).sum()
+    }
+    fn total_capacity(&self) -> usize {
+        self.compactors.iter().map(|c| c.capacity).sum()
+    }
 }
 
 impl<T, F, const LAZY: bool> Compactors<T, F, LAZY>
 where
     T: Ord,
     T: Clone,
-    F: FnMut(Vec<Compactor<T>>),
+    F: FrameHandler<T>,
 {
     pub fn new(k: usize, frame_handler: F) -> Self
     where
-        F: FnMut(Vec<Compactor<T>>),
+        F: FrameHandler<T>,
     {
+        Self::with_capacity_coefficient(k, CAPACITY_COEFFICIENT, frame_handler)
+    }
+    // Like new, but with levels that shrink by `capacity_coefficient` instead of the usual 0.7.
+    // Coefficients closer to 1 keep more items for a smaller error.
+    pub fn with_capacity_coefficient(
+        k: usize,
+        capacity_coefficient: f64,
+        frame_handler: F,
+    ) -> Self {
         Self {
-            compactors: vec![RefCell::new(Compactor::new(capacity(k, 1, 0)))],
+            compactors: vec![Compactor::new(capacity(k, capacity_coefficient, 1, 0))],
             num_compactions: 0,
+            items_seen: 0,
+            on_promote: None,
+            on_merge: None,
+            item_frames: true,
             k,
+            capacity_coefficient,
             frame_handler,
         }
     }
+    // Records frames for compactions only, which keeps long streams watchable.
+    pub fn without_item_frames(self) -> Self {
+        Self {
+            item_frames: false,
+            ..self
+        }
+    }
+    // A copy of every level, leaving the sketch as it is.
+    pub fn snapshot(&self) -> Vec<Compactor<T>> {
+        self.compactors.clone()
+    }
     pub fn update(&mut self, element: T) {
-        self.compactors[0].borrow_mut().update(element);
-        self.record_frame();
+        self.items_seen += 1;
+        self.compactors[0].update(element);
+        if self.item_frames {
+            self.record_frame(FrameEvent::Inserted);
+        }
         self.compact();
     }
 
-    fn size(&self) -> usize {
-        self.compactors.iter().map(|c| c.borrow().data.len()).sum()
+    // Builds the same sketch as updating with each item in turn, but appends to level 0 in bulk
+    // and only looks for compactions once enough items have arrived to need one.
+    pub fn extend<I: IntoIterator<Item = T>>(&mut self, items: I) {
+        let mut items = items.into_iter();
+        loop {
+            let quiet = self.items_before_compaction() - 1;
+            if F::ENABLED && self.item_frames {
+                for element in items.by_ref().take(quiet) {
+                    self.insert(element);
+                    self.record_frame(FrameEvent::Inserted);
+                }
+            } else {
+                let level = &mut self.compactors[0].data;
+                let before = level.len();
+                level.extend(items.by_ref().take(quiet));
+                let added = level.len() - before;
+                self.items_seen += added;
+                self.num_compactions += added as u32;
+            }
+            match items.next() {
+                Some(element) => self.update(element),
+                None => break,
+            }
+        }
     }
-    fn total_capacity(&self) -> usize {
-        self.compactors.iter().map(|c| c.borrow().capacity).sum()
+
+    // Adds an item that is known not to need a compaction. Every update counts towards
+    // num_compactions, whose parity picks the items to promote, so this does too.
+    fn insert(&mut self, element: T) {
+        self.items_seen += 1;
+        self.num_compactions += 1;
+        self.compactors[0].update(element);
+    }
+
+    // How many more items will fit before the last of them makes an update compact, at least 1.
+    // Only level 0 grows between compactions.
+    fn items_before_compaction(&self) -> usize {
+        let level_0 = &self.compactors[0];
+        let until_full = if self.compactors[1..]
+            .iter()
+            .any(|c| c.data.len() >= c.capacity)
+        {
+            0
+        } else {
+            level_0.capacity.saturating_sub(level_0.data.len())
+        };
+        let until_over_capacity = if LAZY {
+            self.total_capacity().saturating_sub(self.size())
+        } else {
+            0
+        };
+        max(max(until_full, until_over_capacity), 1)
+    }
+
+    // Appends every level of `other` to the same level of this sketch, then compacts until the
+    // combined levels fit again.
+    pub fn merge<G>(&mut self, other: Compactors<T, G, LAZY>)
+    where
+        G: FrameHandler<T>,
+    {
+        self.record_frame(FrameEvent::Merging {
+            items: other.items_seen,
+        });
+        let items_seen = self.items_seen;
+        self.items_seen += other.items_seen;
+        let mut other: Vec<Compactor<T>> = other.into();
+        if let Some(on_merge) = self.on_merge {
+            for element in other.iter_mut().flat_map(|c| c.data.iter_mut()) {
+                on_merge(element, items_seen);
+            }
+        }
+        self.grow_to_include_level(other.len() - 1);
+        for (level, compactor) in other.into_iter().enumerate() {
+            let merged = &mut self.compactors[level];
+            if level == 0 {
+                merged.data.extend(compactor.data);
+            } else {
+                merge_sorted(&mut merged.data, compactor.data);
+            }
+            merged.compactions += compactor.compactions;
+            self.record_frame(FrameEvent::Merged { level });
+        }
+        while self.needs_compaction() {
+            self.compact();
+        }
     }
+
     fn capacity(&self, level: usize) -> usize {
-        capacity(self.k, self.compactors.len(), level)
+        capacity(
+            self.k,
+            self.capacity_coefficient,
+            self.compactors.len(),
+            level,
+        )
     }
     fn grow_to_include_level(&mut self, level: usize) {
-        if self.compactors.len() - 1 >= level {
+        if self.compactors.len() > level {
             return;
         }
         // Initialize with 0 because we reset them immediately after.
         self.compactors
-            .resize_with(max(level + 1, self.compactors.len()), || {
-                RefCell::new(Compactor::new(0))
-            });
-        for (level, compactor) in self.compactors.iter().enumerate() {
-            compactor.borrow_mut().capacity = self.capacity(level);
+            .resize_with(max(level + 1, self.compactors.len()), || Compactor::new(0));
+        for level in 0..self.compactors.len() {
+            self.compactors[level].capacity = self.capacity(level);
         }
     }
+    fn needs_compaction(&self) -> bool {
+        (!LAZY || self.size() >= self.total_capacity())
+            && self.compactors.iter().any(|c| c.data.len() >= c.capacity)
+    }
     fn compact(&mut self) {
         self.num_compactions += 1;
         if LAZY {
             for level in 0..self.compactors.len() {
                 if self.size() >= self.total_capacity() {
-                    if self.compactors[level].borrow().data.len()
-                        >= self.compactors[level].borrow().capacity
-                    {
+                    if self.compactors[level].data.len() >= self.compactors[level].capacity {
                         self.refactor_level(level);
                     }
                 } else {
@@ -94,9 +283,7 @@ where
             }
         } else {
             for level in 0..self.compactors.len() {
-                if self.compactors[level].borrow().data.len()
-                    >= self.compactors[level].borrow().capacity
-                {
+                if self.compactors[level].data.len() >= self.compactors[level].capacity {
                     self.refactor_level(level);
                 }
             }
@@ -105,25 +292,90 @@ where
 
     fn refactor_level(&mut self, level: usize) {
         self.grow_to_include_level(level + 1);
-        let use_evens = self.num_compactions % 2 == 0;
-        self.compactors[level].borrow_mut().data.sort();
-        self.record_frame();
-        let mut compactor = Vec::new();
-        std::mem::swap(
-            &mut compactor,
-            &mut self.compactors[level].borrow_mut().data,
-        );
-        self.compactors[level + 1].borrow_mut().data.extend(
-            compactor
-                .into_iter()
-                .enumerate()
-                .filter(|(i, _)| (i % 2 == 0) == use_evens)
-                .map(|(_, element)| element),
-        );
-        self.compactors[level].borrow_mut().data = Vec::new();
-        self.record_frame();
-    }
-    fn record_frame(&mut self) {
-        (self.frame_handler)(self.compactors.iter().map(|c| c.borrow().clone()).collect());
+        let use_evens = self.num_compactions.is_multiple_of(2);
+        // Only level 0 takes items in any order, the others are kept sorted as runs are merged in.
+        if level == 0 {
+            self.compactors[level].data.sort();
+        }
+        debug_assert!(self.compactors[level]
+            .data
+            .windows(2)
+            .all(|pair| pair[0] <= pair[1]));
+        self.record_frame(FrameEvent::Sorted { level });
+        self.record_frame(FrameEvent::Selected { level, use_evens });
+        let (on_promote, items_seen) = (self.on_promote, self.items_seen);
+        let (lower, upper) = self.compactors.split_at_mut(level + 1);
+        let (compactor, next) = (&mut lower[level], &mut upper[0]);
+        let promoted = std::mem::take(&mut compactor.data)
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| (i % 2 == 0) == use_evens)
+            .map(|(_, mut element)| {
+                if let Some(on_promote) = on_promote {
+                    on_promote(&mut element, items_seen);
+                }
+                element
+            })
+            .collect();
+        merge_sorted(&mut next.data, promoted);
+        compactor.compactions += 1;
+        self.record_frame(FrameEvent::Compacted { level });
+    }
+    fn record_frame(&mut self, event: FrameEvent) {
+        if !F::ENABLED {
+            return;
+        }
+        let compactors = self.snapshot();
+        self.frame_handler.handle(Frame {
+            event,
+            items_seen: self.items_seen,
+            compactors,
+        });
+    }
+}
+
+impl<T, F, const LAZY: bool> Extend<T> for Compactors<T, F, LAZY>
+where
+    T: Ord,
+    T: Clone,
+    F: FrameHandler<T>,
+{
+    fn extend<I: IntoIterator<Item = T>>(&mut self, items: I) {
+        Compactors::extend(self, items)
+    }
+}
+
+impl<T, F, const LAZY: bool> FromIterator<T> for Compactors<T, F, LAZY>
+where
+    T: Ord,
+    T: Clone,
+    F: FrameHandler<T>,
+    F: Default,
+{
+    fn from_iter<I: IntoIterator<Item = T>>(items: I) -> Self {
+        let mut compactors = Self::new(DEFAULT_K, F::default());
+        compactors.extend(items);
+        compactors
+    }
+}
+
+impl<T, F, const LAZY: bool> Compactors<Tracked<T>, F, LAZY>
+where
+    T: Ord,
+    T: Clone,
+    F: FrameHandler<Tracked<T>>,
+{
+    // Like new, but every element records its provenance as it moves up through the levels. Items
+    // merged in from another sketch are numbered as if they had arrived after this one's.
+    pub fn new_tracked(k: usize, frame_handler: F) -> Self {
+        Self {
+            on_promote: Some(Tracked::promote),
+            on_merge: Some(Tracked::rebase),
+            ..Self::new(k, frame_handler)
+        }
+    }
+    pub fn update_tracked(&mut self, value: T) {
+        let id = self.items_seen;
+        self.update(Tracked::new(value, id));
     }
 }
@@ -0,0 +1,49 @@
+use crate::{compactor::Compactor, stats::Stats};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrameEvent {
+    Inserted,
+    Sorted { level: usize },
+    // The items of the sorted level at even or odd positions have been chosen for promotion.
+    Selected { level: usize, use_evens: bool },
+    Compacted { level: usize },
+    // Another sketch of `items` items is about to be merged into this one.
+    Merging { items: usize },
+    // The items of the other sketch's level have been appended to the same level of this one.
+    Merged { level: usize },
+}
+
+#[derive(Clone, Debug)]
+pub struct Frame<T: Ord> {
+    pub event: FrameEvent,
+    pub items_seen: usize,
+    pub compactors: Vec<Compactor<T>>,
+}
+
+impl<T: Ord> Frame<T> {
+    pub fn stats(&self) -> Stats {
+        Stats::new(&self.compactors, self.items_seen)
+    }
+}
+
+// Receives every step of the algorithm as it happens.
+pub trait FrameHandler<T: Ord> {
+    // When false, frames are never built, so the sketch pays nothing for them.
+    const ENABLED: bool = true;
+    fn handle(&mut self, frame: Frame<T>);
+}
+
+impl<T: Ord, F: FnMut(Frame<T>)> FrameHandler<T> for F {
+    fn handle(&mut self, frame: Frame<T>) {
+        self(frame)
+    }
+}
+
+// For sketches that are used rather than watched.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NoFrames;
+
+impl<T: Ord> FrameHandler<T> for NoFrames {
+    const ENABLED: bool = false;
+    fn handle(&mut self, _frame: Frame<T>) {}
+}
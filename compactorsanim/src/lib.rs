@@ -1,2 +1,7 @@
 pub mod compactor;
 pub mod compactors;
+pub mod frame;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+pub mod stats;
+pub mod tracked;
@@ -0,0 +1,27 @@
+use std::cmp::max;
+
+use rayon::prelude::*;
+
+use crate::{compactors::Compactors, frame::NoFrames};
+
+// Splits `items` into one chunk per thread, sketches each chunk on its own thread and merges the
+// sketches into one. Merging adds the compactions of every sketch, so the error bound of the
+// result covers the error of the parts as well as of the merge.
+pub fn build<T, const LAZY: bool>(k: usize, items: &[T]) -> Compactors<T, NoFrames, LAZY>
+where
+    T: Ord + Clone + Send + Sync,
+{
+    let chunk_size = max(items.len().div_ceil(rayon::current_num_threads()), 1);
+    items
+        .par_chunks(chunk_size)
+        .map(|chunk| {
+            let mut compactors = Compactors::new(k, NoFrames);
+            compactors.extend(chunk.iter().cloned());
+            compactors
+        })
+        .reduce_with(|mut compactors, other| {
+            compactors.merge(other);
+            compactors
+        })
+        .unwrap_or_else(|| Compactors::new(k, NoFrames))
+}
@@ -0,0 +1,24 @@
+use crate::compactor::Compactor;
+
+// How much space a sketch uses compared to the stream it summarizes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    // Items currently held across all levels.
+    pub retained: usize,
+    pub total_capacity: usize,
+    pub levels: usize,
+    pub compactions: usize,
+    pub items_seen: usize,
+}
+
+impl Stats {
+    pub fn new<T: Ord>(compactors: &[Compactor<T>], items_seen: usize) -> Self {
+        Self {
+            retained: compactors.iter().map(|c| c.data.len()).sum(),
+            total_capacity: compactors.iter().map(|c| c.capacity).sum(),
+            levels: compactors.len(),
+            compactions: compactors.iter().map(|c| c.compactions).sum(),
+            items_seen,
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use std::cmp::Ordering;
+
+// An element that remembers where it came from, so that renderers can follow individual items
+// through the sketch even when values repeat.
+#[derive(Clone, Debug)]
+pub struct Tracked<T> {
+    pub value: T,
+    pub provenance: Provenance,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Provenance {
+    // The index of the item in the input stream, which also identifies it.
+    pub id: usize,
+    // For every level the item has passed through, how many items had been seen when it was
+    // promoted out of that level.
+    pub promotions: Vec<usize>,
+}
+
+impl<T> Tracked<T> {
+    pub fn new(value: T, id: usize) -> Self {
+        Self {
+            value,
+            provenance: Provenance {
+                id,
+                promotions: Vec::new(),
+            },
+        }
+    }
+
+    pub fn id(&self) -> usize {
+        self.provenance.id
+    }
+
+    pub(crate) fn promote(&mut self, items_seen: usize) {
+        self.provenance.promotions.push(items_seen);
+    }
+
+    // Numbers an item of a sketch merged into one that had seen `items_seen` items as if its
+    // stream had followed that one's, so that ids stay unique.
+    pub(crate) fn rebase(&mut self, items_seen: usize) {
+        self.provenance.id += items_seen;
+        for promotion in &mut self.provenance.promotions {
+            *promotion += items_seen;
+        }
+    }
+}
+
+impl<T: PartialEq> PartialEq for Tracked<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value && self.id() == other.id()
+    }
+}
+
+impl<T: Eq> Eq for Tracked<T> {}
+
+impl<T: Ord> PartialOrd for Tracked<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Orders by value, breaking ties by insertion order so that sorting is deterministic.
+impl<T: Ord> Ord for Tracked<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.value
+            .cmp(&other.value)
+            .then_with(|| self.id().cmp(&other.id()))
+    }
+}
@@ -0,0 +1,115 @@
+use compactorsanim::{
+    compactor::Compactor,
+    compactors::Compactors,
+    frame::{Frame, FrameEvent},
+    stats::Stats,
+};
+use rand::{prelude::StdRng, Rng, SeedableRng};
+
+const KS: [usize; 3] = [1, 2, 3];
+const LENGTHS: [usize; 5] = [0, 1, 7, 100, 2_000];
+const SEEDS: u64 = 8;
+
+type Level = (Vec<u32>, usize, usize);
+
+fn levels(compactors: &[Compactor<u32>]) -> Vec<Level> {
+    compactors
+        .iter()
+        .map(|c| (c.data.clone(), c.capacity, c.compactions))
+        .collect()
+}
+
+// Everything a frame shows, since frames cannot be compared directly.
+fn frame(frame: Frame<u32>) -> (FrameEvent, usize, Vec<Level>) {
+    (frame.event, frame.items_seen, levels(&frame.compactors))
+}
+
+struct Sketched {
+    levels: Vec<Level>,
+    stats: Stats,
+    frames: Vec<(FrameEvent, usize, Vec<Level>)>,
+}
+
+fn sequential<const LAZY: bool>(k: usize, items: &[u32]) -> Sketched {
+    let mut frames = Vec::new();
+    let mut compactors: Compactors<u32, _, LAZY> =
+        Compactors::new(k, |f: Frame<u32>| frames.push(frame(f)));
+    for &item in items {
+        compactors.update(item);
+    }
+    let (levels, stats) = (levels(compactors.levels()), compactors.stats());
+    drop(compactors);
+    Sketched {
+        levels,
+        stats,
+        frames,
+    }
+}
+
+// Adds the items in batches of random sizes, some of them empty.
+fn batched<const LAZY: bool>(
+    k: usize,
+    items: &[u32],
+    r: &mut StdRng,
+    item_frames: bool,
+) -> Sketched {
+    let mut frames = Vec::new();
+    let mut compactors: Compactors<u32, _, LAZY> =
+        Compactors::new(k, |f: Frame<u32>| frames.push(frame(f)));
+    if !item_frames {
+        compactors = compactors.without_item_frames();
+    }
+    let mut rest = items;
+    while !rest.is_empty() {
+        let (batch, next) = rest.split_at(r.gen_range(0..=rest.len().min(4 * k + 8)));
+        compactors.extend(batch.iter().copied());
+        rest = next;
+    }
+    let (levels, stats) = (levels(compactors.levels()), compactors.stats());
+    drop(compactors);
+    Sketched {
+        levels,
+        stats,
+        frames,
+    }
+}
+
+fn check<const LAZY: bool>() {
+    for k in KS {
+        for length in LENGTHS {
+            for seed in 0..SEEDS {
+                let mut r = StdRng::seed_from_u64(seed);
+                let items: Vec<u32> = (0..length).map(|_| r.gen_range(0..100)).collect();
+                let expected = sequential::<LAZY>(k, &items);
+                let context = format!("lazy {}, k {}, length {}, seed {}", LAZY, k, length, seed);
+
+                let actual = batched::<LAZY>(k, &items, &mut r, true);
+                assert_eq!(actual.levels, expected.levels, "levels, {}", context);
+                assert_eq!(actual.stats, expected.stats, "stats, {}", context);
+                assert!(actual.frames == expected.frames, "frames, {}", context);
+
+                // Without item frames, only the Inserted frames are left out.
+                let quiet = batched::<LAZY>(k, &items, &mut r, false);
+                assert_eq!(quiet.levels, expected.levels, "levels, {}", context);
+                assert!(
+                    quiet.frames.iter().eq(expected
+                        .frames
+                        .iter()
+                        .filter(|(event, _, _)| *event != FrameEvent::Inserted)),
+                    "frames without items, {}",
+                    context
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn extend_matches_update_lazy() {
+    check::<true>();
+}
+
+#[test]
+fn extend_matches_update_eager() {
+    check::<false>();
+}
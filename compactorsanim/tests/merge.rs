@@ -0,0 +1,132 @@
+use std::collections::HashSet;
+
+use compactorsanim::{compactors::Compactors, frame::NoFrames, tracked::Tracked};
+use rand::{prelude::StdRng, Rng, SeedableRng};
+
+const KS: [usize; 3] = [2, 10, 50];
+const LENGTHS: [usize; 4] = [0, 1, 100, 3_000];
+const SEEDS: u64 = 4;
+
+type Sketch<const LAZY: bool> = Compactors<Tracked<u32>, NoFrames, LAZY>;
+
+fn sketch<const LAZY: bool>(k: usize, items: &[u32]) -> Sketch<LAZY> {
+    let mut compactors = Sketch::<LAZY>::new_tracked(k, NoFrames);
+    for &item in items {
+        compactors.update_tracked(item);
+    }
+    compactors
+}
+
+fn ids<const LAZY: bool>(compactors: &Sketch<LAZY>) -> Vec<usize> {
+    compactors
+        .levels()
+        .iter()
+        .flat_map(|c| c.data.iter().map(Tracked::id))
+        .collect()
+}
+
+// The largest distance between the rank the sketch gives any item of the stream and its true
+// rank, the number of items less than or equal to it.
+fn max_rank_error<const LAZY: bool>(compactors: &Sketch<LAZY>, sorted: &[u32]) -> usize {
+    let mut weighted: Vec<(u32, usize)> = compactors
+        .levels()
+        .iter()
+        .enumerate()
+        .flat_map(|(level, c)| c.data.iter().map(move |item| (item.value, 1 << level)))
+        .collect();
+    weighted.sort_unstable();
+    let mut weighted = weighted.into_iter().peekable();
+    let mut estimate = 0;
+    let mut max_error = 0;
+    for (i, &item) in sorted.iter().enumerate() {
+        if sorted.get(i + 1) == Some(&item) {
+            continue;
+        }
+        while let Some((_, weight)) = weighted.next_if(|&(x, _)| x <= item) {
+            estimate += weight;
+        }
+        max_error = max_error.max(estimate.abs_diff(i + 1));
+    }
+    max_error
+}
+
+fn check_merged<const LAZY: bool>(merged: &Sketch<LAZY>, sorted: &[u32], context: &str) {
+    let ids = ids(merged);
+    assert_eq!(
+        ids.iter().collect::<HashSet<_>>().len(),
+        ids.len(),
+        "unique ids, {}",
+        context
+    );
+    assert!(
+        ids.iter().all(|&id| id < sorted.len()),
+        "ids within the stream, {}",
+        context
+    );
+    assert_eq!(
+        merged.stats().items_seen,
+        sorted.len(),
+        "items seen, {}",
+        context
+    );
+    for (level, c) in merged.levels().iter().enumerate().skip(1) {
+        assert!(
+            c.data.windows(2).all(|pair| pair[0] <= pair[1]),
+            "level {} sorted, {}",
+            level,
+            context
+        );
+    }
+    let error = max_rank_error(merged, sorted);
+    assert!(
+        error <= merged.rank_error_bound(),
+        "rank error {} over the bound of {}, {}",
+        error,
+        merged.rank_error_bound(),
+        context
+    );
+}
+
+fn check<const LAZY: bool>() {
+    for k in KS {
+        for length in LENGTHS {
+            for other_length in LENGTHS {
+                for seed in 0..SEEDS {
+                    let mut r = StdRng::seed_from_u64(seed);
+                    let items: Vec<u32> = (0..length).map(|_| r.gen_range(0..1_000)).collect();
+                    let others: Vec<u32> =
+                        (0..other_length).map(|_| r.gen_range(0..1_000)).collect();
+                    let context = format!(
+                        "lazy {}, k {}, lengths {} and {}, seed {}",
+                        LAZY, k, length, other_length, seed
+                    );
+
+                    let mut merged = sketch::<LAZY>(k, &items);
+                    merged.merge(sketch::<LAZY>(k, &others));
+                    let mut sorted: Vec<u32> = items.iter().chain(&others).copied().collect();
+                    sorted.sort_unstable();
+                    check_merged(&merged, &sorted, &context);
+
+                    // Items that arrive after the merge are numbered after both streams.
+                    let later: Vec<u32> = (0..length).map(|_| r.gen_range(0..1_000)).collect();
+                    for &item in &later {
+                        merged.update_tracked(item);
+                    }
+                    sorted.extend(&later);
+                    sorted.sort_unstable();
+                    check_merged(&merged, &sorted, &format!("{}, after updates", context));
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn merge_tracked_lazy() {
+    check::<true>();
+}
+
+#[test]
+fn merge_tracked_eager() {
+    check::<false>();
+}
@@ -0,0 +1,64 @@
+use compactorsanim::{compactors::Compactors, frame::NoFrames, parallel};
+use rand::{prelude::StdRng, Rng, SeedableRng};
+
+const K: usize = 100;
+const LENGTH: usize = 200_000;
+// More than one, so there is always something to merge, even on a single core.
+const THREADS: usize = 8;
+
+// The largest distance between the rank the sketch gives any item of the stream and its true
+// rank, the number of items less than or equal to it.
+fn max_rank_error<const LAZY: bool>(
+    compactors: &Compactors<u32, NoFrames, LAZY>,
+    sorted: &[u32],
+) -> usize {
+    let mut weighted: Vec<(u32, usize)> = compactors
+        .levels()
+        .iter()
+        .enumerate()
+        .flat_map(|(level, c)| c.data.iter().map(move |&item| (item, 1 << level)))
+        .collect();
+    weighted.sort_unstable();
+    let mut weighted = weighted.into_iter().peekable();
+    let mut estimate = 0;
+    let mut max_error = 0;
+    for (i, &item) in sorted.iter().enumerate() {
+        if sorted.get(i + 1) == Some(&item) {
+            continue;
+        }
+        while let Some((_, weight)) = weighted.next_if(|&(x, _)| x <= item) {
+            estimate += weight;
+        }
+        max_error = max_error.max(estimate.abs_diff(i + 1));
+    }
+    max_error
+}
+
+#[test]
+fn parallel_error_within_bound() {
+    let mut r = StdRng::seed_from_u64(42);
+    let items: Vec<u32> = (0..LENGTH).map(|_| r.gen_range(0..LENGTH as u32)).collect();
+    let mut sorted = items.clone();
+    sorted.sort_unstable();
+
+    let mut single: Compactors<u32, NoFrames, true> = Compactors::new(K, NoFrames);
+    single.extend(items.iter().copied());
+    let merged = rayon::ThreadPoolBuilder::new()
+        .num_threads(THREADS)
+        .build()
+        .unwrap()
+        .install(|| parallel::build::<_, true>(K, &items));
+
+    assert_eq!(merged.stats().items_seen, LENGTH);
+    let single_error = max_rank_error(&single, &sorted);
+    let merged_error = max_rank_error(&merged, &sorted);
+    assert!(single_error <= single.rank_error_bound());
+    assert!(
+        merged_error <= merged.rank_error_bound(),
+        "rank error {} is over the bound of {}, where a single thread has {} of {}",
+        merged_error,
+        merged.rank_error_bound(),
+        single_error,
+        single.rank_error_bound()
+    );
+}
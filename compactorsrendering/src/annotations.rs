@@ -0,0 +1,96 @@
+use compactorsanim::{
+    compactor::Compactor,
+    frame::{Frame, FrameEvent},
+    tracked::Tracked,
+};
+use ggez::graphics::Color;
+use renderingcommon::{
+    annotations::{self, draw_header},
+    font,
+    renderer::Renderer,
+    theme::Theme,
+    Element,
+};
+
+use crate::compactor_element_to_rect;
+
+// Room for labels up to "level 10, weight 1024, 100/100".
+pub const LABEL_WIDTH: u32 = 31 * font::ADVANCE;
+
+fn legend(theme: &Theme) -> [(&'static str, Color); 4] {
+    [
+        ("item", theme.element),
+        ("discarded", theme.discarded_element),
+        ("used capacity", theme.used_capacity),
+        ("free capacity", theme.capacity),
+    ]
+}
+
+pub fn render(frame: &Frame<Tracked<Element>>, theme: &Theme, renderer: &mut dyn Renderer) {
+    draw_header(
+        renderer,
+        theme,
+        [&caption(frame.event), &items_seen(frame)],
+        &legend(theme),
+    );
+    render_levels(&frame.compactors, theme, renderer);
+}
+
+pub fn render_levels(
+    compactors: &[Compactor<Tracked<Element>>],
+    theme: &Theme,
+    renderer: &mut dyn Renderer,
+) {
+    let layout = &theme.layout;
+    let x = layout.space_between_elements as f32;
+    for (level, compactor) in compactors.iter().enumerate() {
+        let row = compactor_element_to_rect(layout, level, 0, layout.max_element_height);
+        renderer.text(
+            x,
+            row.y + (layout.max_element_height.saturating_sub(font::GLYPH_HEIGHT) / 2) as f32,
+            &format!(
+                "level {}, weight {}, {}/{}",
+                level,
+                1u64 << level,
+                compactor.data.len(),
+                compactor.capacity
+            ),
+            theme.text,
+        );
+    }
+}
+
+pub fn header_width(frame: &Frame<Tracked<Element>>, theme: &Theme) -> u32 {
+    annotations::header_width(
+        theme,
+        [&caption(frame.event), &items_seen(frame)],
+        &legend(theme),
+    )
+}
+
+fn items_seen(frame: &Frame<Tracked<Element>>) -> String {
+    format!("items seen: {}", frame.items_seen)
+}
+
+fn caption(event: FrameEvent) -> String {
+    match event {
+        FrameEvent::Inserted => "Inserted an item into level 0".to_string(),
+        FrameEvent::Sorted { level } => format!("Level {} is full, sorted it", level),
+        FrameEvent::Selected { level, use_evens } => format!(
+            "Keeping the items at {} positions of level {}",
+            if use_evens { "even" } else { "odd" },
+            level
+        ),
+        FrameEvent::Compacted { level } => format!(
+            "Compacted level {}, promoting every other item to level {}",
+            level,
+            level + 1
+        ),
+        FrameEvent::Merging { items } => {
+            format!("Merging a sketch of {} items into this one", items)
+        }
+        FrameEvent::Merged { level } => {
+            format!("Appended level {} of the other sketch to this one", level)
+        }
+    }
+}
@@ -1,167 +1,326 @@
+mod annotations;
+mod merge;
+mod svg;
+mod timeline;
+mod tween;
+
 use renderingcommon::{
-    Element, CAPACITY_RECT_COLOR, ELEMENT_WIDTH, MAX_ELEMENT_HEIGHT, SPACE_BETWEEN_ELEMENTS,
-    USED_CAPACITY_RECT_COLOR,
+    annotations::header_height,
+    options::{Options, Program},
+    output::OutputFormat,
+    renderer::{render_frames, Renderer},
+    scale::ValueScale,
+    theme::{Layout, Theme},
+    viewer,
+    viewport::Viewport,
+    Element,
 };
-use std::{cmp::max, fs::File};
+use std::{cmp::max, time::Duration};
 
 use ggez::{
-    conf::{NumSamples, WindowMode},
-    event::EventHandler,
-    graphics::{
-        self, get_window_color_format, Canvas, Color, DrawMode, DrawParam, MeshBuilder, Rect,
-    },
-    GameError, GameResult,
+    graphics::{Color, Rect},
+    GameResult,
 };
-use png::{BitDepth, ColorType};
 use rand::{
     prelude::{Distribution, StdRng},
     SeedableRng,
 };
 
-use compactorsanim::compactor::Compactor;
-use rand_distr::Normal;
+use compactorsanim::{
+    compactor::Compactor,
+    frame::{Frame, FrameEvent, FrameHandler},
+    tracked::Tracked,
+};
 
+use annotations::LABEL_WIDTH;
 use compactorsanim::compactors::Compactors;
+use timeline::Timeline;
+use tween::DisplayFrame;
 
-struct MainState<I: Iterator<Item = Vec<Compactor<Element>>>> {
-    frames: I,
-    current_frame: Vec<Compactor<Element>>,
+fn render(
+    frame: &Frame<Tracked<Element>>,
+    theme: &Theme,
+    scale: &ValueScale,
+    renderer: &mut dyn Renderer,
+) {
+    renderer.clear(theme.background);
+    for (rect, color) in compactor_rects(&frame.compactors, frame.event, theme, scale) {
+        renderer.fill_rect(rect, color);
+    }
+    annotations::render(frame, theme, renderer);
 }
 
-impl<I: Iterator<Item = Vec<Compactor<Element>>>> EventHandler<GameError> for MainState<I> {
-    fn update(&mut self, _ctx: &mut ggez::Context) -> Result<(), GameError> {
-        Ok(())
-    }
+fn render_with_incoming(
+    frame: &Frame<Tracked<Element>>,
+    incoming: &[Compactor<Tracked<Element>>],
+    incoming_x: f32,
+    theme: &Theme,
+    scale: &ValueScale,
+    renderer: &mut dyn Renderer,
+) {
+    render(frame, theme, scale, renderer);
+    merge::render_incoming(incoming, frame.event, incoming_x, theme, scale, renderer);
+}
 
-    fn draw(&mut self, ctx: &mut ggez::Context) -> Result<(), GameError> {
-        graphics::clear(ctx, Color::BLACK);
-        let mut mesh_builder = MeshBuilder::new();
-        let mut used_capacity: i32 = self.current_frame.iter().map(|c| c.data.len() as i32).sum();
-        for (level, compactor) in self.current_frame.iter().enumerate() {
-            for (i, capacity_rect) in capacity_rects(level, compactor.capacity).enumerate() {
-                let color = if (i as i32) < used_capacity {
-                    USED_CAPACITY_RECT_COLOR
-                } else {
-                    CAPACITY_RECT_COLOR
-                };
-                mesh_builder.rectangle(DrawMode::fill(), capacity_rect, color)?;
-            }
-            used_capacity -= compactor.capacity as i32;
-            for (element_index, &element) in compactor.data.iter().enumerate() {
-                mesh_builder.rectangle(
-                    DrawMode::fill(),
-                    compactor_element_to_rect(level, element_index, element),
-                    Color::RED,
-                )?;
-            }
+fn compactor_rects(
+    compactors: &[Compactor<Tracked<Element>>],
+    event: FrameEvent,
+    theme: &Theme,
+    scale: &ValueScale,
+) -> Vec<(Rect, Color)> {
+    let mut rects = compactor_capacity_rects(compactors, theme);
+    for (level, compactor) in compactors.iter().enumerate() {
+        for (element_index, element) in compactor.data.iter().enumerate() {
+            rects.push((
+                compactor_element_to_rect(
+                    &theme.layout,
+                    level,
+                    element_index,
+                    element_height(&theme.layout, scale, element),
+                ),
+                element_color(theme, event, level, element_index),
+            ));
         }
-        let mesh = mesh_builder.build(ctx)?;
-        graphics::draw(ctx, &mesh, DrawParam::default())?;
-        graphics::present(ctx)
     }
+    rects
+}
 
-    fn key_down_event(
-        &mut self,
-        ctx: &mut ggez::Context,
-        keycode: ggez::event::KeyCode,
-        _keymods: ggez::event::KeyMods,
-        _repeat: bool,
-    ) {
-        if keycode == ggez::event::KeyCode::Escape {
-            return ggez::event::quit(ctx);
+// While a level is being compacted, the items that will not be promoted are dimmed.
+fn element_color(theme: &Theme, event: FrameEvent, level: usize, element_index: usize) -> Color {
+    match event {
+        FrameEvent::Selected {
+            level: selected_level,
+            use_evens,
+        } if selected_level == level && element_index.is_multiple_of(2) != use_evens => {
+            theme.discarded_element
         }
-        if let Some(frame) = self.frames.next() {
-            self.current_frame = frame;
+        _ => theme.element,
+    }
+}
+
+fn compactor_capacity_rects(
+    compactors: &[Compactor<Tracked<Element>>],
+    theme: &Theme,
+) -> Vec<(Rect, Color)> {
+    let mut rects = Vec::new();
+    let mut used_capacity: i32 = compactors.iter().map(|c| c.data.len() as i32).sum();
+    for (level, compactor) in compactors.iter().enumerate() {
+        for (i, capacity_rect) in
+            capacity_rects(&theme.layout, level, compactor.capacity).enumerate()
+        {
+            let color = if (i as i32) < used_capacity {
+                theme.used_capacity
+            } else {
+                theme.capacity
+            };
+            rects.push((capacity_rect, color));
         }
+        used_capacity -= compactor.capacity as i32;
     }
+    rects
 }
 
-fn capacity_rects(level: usize, capacity: usize) -> impl Iterator<Item = Rect> {
+fn capacity_rects(layout: &Layout, level: usize, capacity: usize) -> impl Iterator<Item = Rect> {
+    let layout = *layout;
     (0..capacity).map(move |element_index| {
-        compactor_element_to_rect(level, element_index, MAX_ELEMENT_HEIGHT)
+        compactor_element_to_rect(&layout, level, element_index, layout.max_element_height)
     })
 }
 
-fn compactor_element_to_rect(level: usize, element_index: usize, element: Element) -> Rect {
+fn element_height(layout: &Layout, scale: &ValueScale, element: &Tracked<Element>) -> u32 {
+    scale.height(element.value as f32, layout.max_element_height)
+}
+
+fn compactor_element_to_rect(
+    layout: &Layout,
+    level: usize,
+    element_index: usize,
+    height: u32,
+) -> Rect {
+    let space = layout.space_between_elements;
     Rect::new_i32(
-        (SPACE_BETWEEN_ELEMENTS + element_index as u32 * (SPACE_BETWEEN_ELEMENTS + ELEMENT_WIDTH))
-            as i32,
-        (SPACE_BETWEEN_ELEMENTS + level as u32 * (MAX_ELEMENT_HEIGHT + SPACE_BETWEEN_ELEMENTS))
-            as i32,
-        ELEMENT_WIDTH as i32,
-        element as i32,
+        (LABEL_WIDTH + space + element_index as u32 * (space + layout.element_width)) as i32,
+        (header_height(layout) + space + level as u32 * (layout.max_element_height + space)) as i32,
+        layout.element_width as i32,
+        height as i32,
     )
 }
 
-fn frame_size(compactors: &Vec<Compactor<Element>>) -> (u32, u32) {
-    return (
-        SPACE_BETWEEN_ELEMENTS
+fn frame_size(frame: &Frame<Tracked<Element>>, theme: &Theme) -> (u32, u32) {
+    let (width, height) = compactors_size(&frame.compactors, &theme.layout);
+    (max(width, annotations::header_width(frame, theme)), height)
+}
+
+// The size of the labelled levels, including the header above them.
+fn compactors_size(compactors: &[Compactor<Tracked<Element>>], layout: &Layout) -> (u32, u32) {
+    let space = layout.space_between_elements;
+    (
+        LABEL_WIDTH
+            + space
             + compactors
                 .iter()
-                .map(|c| c.data.len() as u32 * (ELEMENT_WIDTH + SPACE_BETWEEN_ELEMENTS))
+                .map(|c| max(c.data.len(), c.capacity) as u32 * (layout.element_width + space))
                 .max()
                 .unwrap_or(0),
-        SPACE_BETWEEN_ELEMENTS
-            + compactors.len() as u32 * (MAX_ELEMENT_HEIGHT + SPACE_BETWEEN_ELEMENTS),
-    );
+        header_height(layout)
+            + space
+            + compactors.len() as u32 * (layout.max_element_height + space),
+    )
 }
 
-fn make_frames<D: Distribution<f32>, const LAZY: bool>(d: D) -> Vec<Vec<Compactor<Element>>> {
-    let mut frames: Vec<Vec<Compactor<Element>>> = Vec::new();
-    let mut compactors: Compactors<Element, _, LAZY> =
-        Compactors::new(10, |frame| frames.push(frame));
-    let mut r = StdRng::seed_from_u64(42);
-    for _i in 1..100 {
-        compactors.update(d.sample(&mut r) as u32);
+fn frame_delay(event: FrameEvent, delay: Duration) -> Duration {
+    match event {
+        FrameEvent::Inserted => delay,
+        FrameEvent::Sorted { .. } => delay * 2,
+        FrameEvent::Selected { .. } => delay * 4,
+        FrameEvent::Compacted { .. } => delay * 4,
+        FrameEvent::Merging { .. } => delay * 4,
+        FrameEvent::Merged { .. } => delay * 2,
     }
+}
+
+const ITEMS: usize = 99;
+
+fn make_sketch<D, F, const LAZY: bool>(
+    d: &D,
+    seed: u64,
+    items: usize,
+    frame_handler: F,
+) -> Compactors<Tracked<Element>, F, LAZY>
+where
+    D: Distribution<f32>,
+    F: FrameHandler<Tracked<Element>>,
+{
+    let mut compactors = Compactors::new_tracked(10, frame_handler);
+    let mut r = StdRng::seed_from_u64(seed);
+    for _ in 0..items {
+        compactors.update_tracked(d.sample(&mut r) as Element);
+    }
+    compactors
+}
+
+fn make_frames<D: Distribution<f32>, const LAZY: bool>(d: D) -> Vec<Frame<Tracked<Element>>> {
+    let mut frames: Vec<Frame<Tracked<Element>>> = Vec::new();
+    make_sketch::<_, _, LAZY>(&d, 42, ITEMS, |frame| frames.push(frame));
     frames
 }
 
 fn main() -> GameResult {
-    let frames = make_frames::<_, true>(*renderingcommon::DISTRIBUTION);
-    let (mut ctx, _event_loop) = ggez::ContextBuilder::new("quantiles", "jedmonds").build()?;
-    let _mode = WindowMode::default();
+    let options = Options::from_env(Program::Compactors);
+    let (frames, incoming) = if options.merge {
+        merge::make_frames::<_, true>(*renderingcommon::DISTRIBUTION)
+    } else {
+        let frames = make_frames::<_, true>(*renderingcommon::DISTRIBUTION);
+        let incoming = vec![Vec::new(); frames.len()];
+        (frames, incoming)
+    };
+    let theme = options.theme.clone();
+    let scale = ValueScale::new(
+        options.scale,
+        options.scale_range,
+        frames
+            .iter()
+            .flat_map(|frame| frame.compactors.iter())
+            .flat_map(|compactor| compactor.data.iter())
+            .map(|element| element.value as f32),
+    );
     let (w, h) = frames
         .iter()
-        .map(frame_size)
-        .fold((0, 0), |(w1, h1), (w2, h2)| {
-            (max(w1 as u32, w2 as u32), max(h1 as u32, h2 as u32))
-        });
-    let mut frames = frames.into_iter();
-    let color_format = get_window_color_format(&ctx);
-    let canvas = Canvas::new(&mut ctx, w as u16, h as u16, NumSamples::One, color_format)?;
-    graphics::set_screen_coordinates(&mut ctx, Rect::new_i32(0, 0, w as i32, h as i32))?;
-    graphics::set_canvas(&mut ctx, Some(&canvas));
-    let mut state = MainState {
-        current_frame: frames.next().unwrap_or(Vec::new()),
-        frames,
+        .map(|frame| frame_size(frame, &theme))
+        .fold((0, 0), |(w1, h1), (w2, h2)| (max(w1, w2), max(h1, h2)));
+    // The sketch being merged in sits to the right of everything else.
+    let levels_width = w;
+    let (w, h) = incoming
+        .iter()
+        .filter(|incoming| !incoming.is_empty())
+        .map(|incoming| compactors_size(incoming, &theme.layout))
+        .fold((w, h), |(w1, h1), (w2, h2)| (max(w1, w + w2), max(h1, h2)));
+    let timeline = Timeline::new(&frames);
+    let timeline_y = h as f32;
+    let h = h + Timeline::height(&theme.layout);
+    let viewport = Viewport::fit(w, h, &options.viewport)?;
+    let draw = |i: usize, renderer: &mut dyn Renderer| {
+        render_with_incoming(
+            &frames[i],
+            &incoming[i],
+            levels_width as f32,
+            &theme,
+            &scale,
+            renderer,
+        );
+        timeline.render(i, &theme, renderer, timeline_y, levels_width);
     };
-    state.draw(&mut ctx)?;
-    let mut frame_id = 0;
-    save_frame(canvas.to_rgba8(&mut ctx)?, frame_id, w, h);
-    frame_id += 1;
-    while let Some(frame) = state.frames.next() {
-        state.current_frame = frame;
-        graphics::set_canvas(&mut ctx, Some(&canvas));
-        state.draw(&mut ctx)?;
-        save_frame(canvas.to_rgba8(&mut ctx)?, frame_id, w, h);
-        frame_id += 1;
+    match options.output.format {
+        OutputFormat::Svg => {
+            return Ok(svg::write_frames(
+                frames.len(),
+                &draw,
+                &viewport,
+                &options.output,
+            )?)
+        }
+        OutputFormat::AnimatedSvg => {
+            let delays: Vec<Duration> = frames
+                .iter()
+                .map(|frame| frame_delay(frame.event, options.output.frame_delay))
+                .collect();
+            return Ok(svg::write_animation(
+                &delays,
+                &draw,
+                &viewport,
+                &options.output,
+            )?);
+        }
+        _ => {}
     }
-
-    Ok(())
-}
-
-fn save_frame(frame: Vec<u8>, frame_id: u32, width: u32, height: u32) {
-    let mut encoder = png::Encoder::new(
-        File::create(format!("frame{:0w$}.png", frame_id, w = 4)).unwrap(),
-        width,
-        height,
+    let display_frames = tween::display_frames(frames.len(), options.tween_frames);
+    let frame_count = display_frames.len();
+    let (delay, tween_frames, easing) = (
+        options.output.frame_delay,
+        options.tween_frames,
+        options.easing,
     );
-    encoder.set_color(ColorType::Rgba);
-    encoder.set_depth(BitDepth::Eight);
-    encoder
-        .write_header()
-        .unwrap()
-        .write_image_data(&frame)
-        .unwrap();
+    let render_frame = move |i: usize, renderer: &mut dyn Renderer| {
+        let renderer = &mut viewport.apply(renderer);
+        match display_frames[i] {
+            DisplayFrame::Frame(frame) => {
+                render_with_incoming(
+                    &frames[frame],
+                    &incoming[frame],
+                    levels_width as f32,
+                    &theme,
+                    &scale,
+                    renderer,
+                );
+                timeline.render(frame, &theme, renderer, timeline_y, levels_width);
+                frame_delay(frames[frame].event, delay)
+            }
+            DisplayFrame::Tween { from, t } => {
+                tween::render(
+                    &frames[from],
+                    &frames[from + 1],
+                    easing.apply(t),
+                    &theme,
+                    &scale,
+                    renderer,
+                );
+                merge::render_incoming(
+                    &incoming[from + 1],
+                    frames[from + 1].event,
+                    levels_width as f32,
+                    &theme,
+                    &scale,
+                    renderer,
+                );
+                timeline.render(from, &theme, renderer, timeline_y, levels_width);
+                delay / tween_frames
+            }
+        }
+    };
+    let (w, h) = (viewport.width, viewport.height);
+    if options.interactive {
+        viewer::run(w, h, frame_count, render_frame)
+    } else {
+        render_frames(&options, w, h, frame_count, render_frame)
+    }
 }
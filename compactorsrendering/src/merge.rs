@@ -0,0 +1,118 @@
+use compactorsanim::{
+    compactor::Compactor,
+    frame::{Frame, FrameEvent},
+    tracked::Tracked,
+};
+use ggez::{
+    graphics::{Color, Rect},
+    GameResult,
+};
+use rand::prelude::Distribution;
+use rand_distr::Normal;
+use renderingcommon::{font, renderer::Renderer, scale::ValueScale, theme::Theme, Element};
+
+use crate::{annotations, compactor_rects, make_sketch, ITEMS};
+
+// The levels of the sketch being merged in that have not been appended yet, for every frame of
+// the merge. They are drawn to the right of the sketch they are merged into.
+pub type Incoming = Vec<Compactor<Tracked<Element>>>;
+
+// The other sketch is smaller and skewed towards larger values, so that both are recognisable.
+const OTHER_ITEMS: usize = 60;
+
+// Builds a second sketch from another seed and distribution and merges it into the usual one.
+// Only the frames of the merge itself are kept.
+pub fn make_frames<D: Distribution<f32>, const LAZY: bool>(
+    d: D,
+) -> (Vec<Frame<Tracked<Element>>>, Vec<Incoming>) {
+    let other_distribution = Normal::new(16.0, 2.0).unwrap();
+    let mut frames: Vec<Frame<Tracked<Element>>> = Vec::new();
+    let mut other_frames: Vec<Frame<Tracked<Element>>> = Vec::new();
+    {
+        let mut compactors = make_sketch::<_, _, LAZY>(&d, 42, ITEMS, |frame| frames.push(frame));
+        let other = make_sketch::<_, _, LAZY>(&other_distribution, 7, OTHER_ITEMS, |frame| {
+            other_frames.push(frame)
+        });
+        compactors.merge(other);
+    }
+    let start = frames
+        .iter()
+        .position(|frame| matches!(frame.event, FrameEvent::Merging { .. }))
+        .unwrap_or(0);
+    frames.drain(..start);
+    let other = other_frames
+        .pop()
+        .map(|frame| frame.compactors)
+        .unwrap_or_default();
+    let incoming = frames
+        .iter()
+        .map(|frame| match frame.event {
+            FrameEvent::Merging { .. } => other.clone(),
+            // Appended levels are left empty so that the others stay in their rows.
+            FrameEvent::Merged { level } => other
+                .iter()
+                .enumerate()
+                .map(|(other_level, compactor)| Compactor {
+                    data: if other_level <= level {
+                        Vec::new()
+                    } else {
+                        compactor.data.clone()
+                    },
+                    ..compactor.clone()
+                })
+                .collect(),
+            _ => Vec::new(),
+        })
+        .collect();
+    (frames, incoming)
+}
+
+pub fn render_incoming(
+    incoming: &[Compactor<Tracked<Element>>],
+    event: FrameEvent,
+    x: f32,
+    theme: &Theme,
+    scale: &ValueScale,
+    renderer: &mut dyn Renderer,
+) {
+    if incoming.is_empty() {
+        return;
+    }
+    let renderer = &mut Shifted { x, renderer };
+    for (rect, color) in compactor_rects(incoming, event, theme, scale) {
+        renderer.fill_rect(rect, color);
+    }
+    let layout = &theme.layout;
+    renderer.text(
+        layout.space_between_elements as f32,
+        (layout.space_between_elements + font::LINE_HEIGHT) as f32,
+        "other sketch",
+        theme.text,
+    );
+    annotations::render_levels(incoming, theme, renderer);
+}
+
+// Moves everything drawn through it to the right.
+struct Shifted<'a> {
+    x: f32,
+    renderer: &'a mut dyn Renderer,
+}
+
+impl Renderer for Shifted<'_> {
+    fn clear(&mut self, color: Color) {
+        self.renderer.clear(color);
+    }
+
+    fn fill_rect(&mut self, rect: Rect, color: Color) {
+        self.renderer
+            .fill_rect(Rect::new(rect.x + self.x, rect.y, rect.w, rect.h), color);
+    }
+
+    fn scaled_text(&mut self, x: f32, y: f32, scale: f32, text: &str, color: Color) {
+        self.renderer.scaled_text(x + self.x, y, scale, text, color);
+    }
+
+    fn read_pixels(&mut self) -> GameResult<Vec<u8>> {
+        self.renderer.read_pixels()
+    }
+}
@@ -0,0 +1,202 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::PathBuf,
+    time::Duration,
+};
+
+use ggez::{
+    graphics::{Color, Rect},
+    GameError, GameResult,
+};
+use renderingcommon::{font, output::OutputOptions, renderer::Renderer, viewport::Viewport};
+
+const FONT_FAMILY: &str = "'DejaVu Sans Mono', Menlo, Consolas, monospace";
+// Monospace fonts advance about 0.6 em and have capitals about 0.7 em tall, so this matches the
+// 6 pixel advance and 7 pixel glyphs of the bundled font.
+const FONT_SIZE: f32 = 10.0;
+
+// Draws the frame with the given index.
+pub type Draw<'a> = dyn Fn(usize, &mut dyn Renderer) + 'a;
+
+pub fn write_frames(
+    frame_count: usize,
+    draw: &Draw,
+    viewport: &Viewport,
+    options: &OutputOptions,
+) -> io::Result<()> {
+    let directory = options.path.clone().unwrap_or_else(|| PathBuf::from("."));
+    for frame_id in 0..frame_count {
+        let mut w = BufWriter::new(File::create(directory.join(format!(
+            "frame{:0w$}.svg",
+            frame_id,
+            w = 4
+        )))?);
+        write_header(&mut w, viewport)?;
+        write_rects(&mut w, |renderer| draw(frame_id, renderer), viewport)?;
+        writeln!(w, "</svg>")?;
+        w.flush()?;
+    }
+    Ok(())
+}
+
+// Every frame is a group that is only visible between its start and end time. The timings are
+// expressed as discrete keyTimes over the whole animation so that it can loop.
+pub fn write_animation(
+    delays: &[Duration],
+    draw: &Draw,
+    viewport: &Viewport,
+    options: &OutputOptions,
+) -> io::Result<()> {
+    let total: f64 = delays.iter().map(Duration::as_secs_f64).sum();
+    if total <= 0.0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "An animated SVG needs frames that last longer than 0ms; raise --delay",
+        ));
+    }
+    let mut w = BufWriter::new(File::create(
+        options
+            .path
+            .clone()
+            .unwrap_or_else(|| PathBuf::from("animation.svg")),
+    )?);
+    let repeat_count = if options.loop_count == 0 {
+        "indefinite".to_string()
+    } else {
+        options.loop_count.to_string()
+    };
+    write_header(&mut w, viewport)?;
+    let mut start = 0.0;
+    for (frame_id, delay) in delays.iter().enumerate() {
+        let end = start + delay.as_secs_f64();
+        let (values, key_times) = if start <= 0.0 && end >= total {
+            ("visible", "0".to_string())
+        } else if start <= 0.0 {
+            ("visible;hidden", format!("0;{:.6}", end / total))
+        } else if end >= total {
+            ("hidden;visible", format!("0;{:.6}", start / total))
+        } else {
+            (
+                "hidden;visible;hidden",
+                format!("0;{:.6};{:.6}", start / total, end / total),
+            )
+        };
+        writeln!(w, r#"<g visibility="hidden">"#)?;
+        writeln!(
+            w,
+            r#"<animate attributeName="visibility" calcMode="discrete" values="{}" keyTimes="{}" dur="{:.3}s" repeatCount="{}" fill="freeze"/>"#,
+            values, key_times, total, repeat_count
+        )?;
+        write_rects(&mut w, |renderer| draw(frame_id, renderer), viewport)?;
+        writeln!(w, "</g>")?;
+        start = end;
+    }
+    writeln!(w, "</svg>")?;
+    w.flush()
+}
+
+fn write_header<W: Write>(w: &mut W, viewport: &Viewport) -> io::Result<()> {
+    writeln!(
+        w,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}" shape-rendering="crispEdges">"#,
+        viewport.width, viewport.height
+    )
+}
+
+fn write_rects<W: Write>(
+    w: &mut W,
+    draw: impl FnOnce(&mut dyn Renderer),
+    viewport: &Viewport,
+) -> io::Result<()> {
+    let mut renderer = SvgRenderer { w, result: Ok(()) };
+    draw(&mut viewport.apply(&mut renderer));
+    renderer.result
+}
+
+// Writes every rectangle as an SVG rect and every string as SVG text, keeping the first error.
+struct SvgRenderer<'a, W: Write> {
+    w: &'a mut W,
+    result: io::Result<()>,
+}
+
+impl<W: Write> SvgRenderer<'_, W> {
+    fn write_rect(&mut self, x: f32, y: f32, width: f32, height: f32, color: Color) {
+        if self.result.is_ok() {
+            self.result = writeln!(
+                self.w,
+                r#"<rect x="{}" y="{}" width="{}" height="{}" {}/>"#,
+                x,
+                y,
+                width,
+                height,
+                svg_fill(color)
+            );
+        }
+    }
+}
+
+impl<W: Write> Renderer for SvgRenderer<'_, W> {
+    fn clear(&mut self, color: Color) {
+        if self.result.is_ok() {
+            self.result = writeln!(
+                self.w,
+                r#"<rect width="100%" height="100%" {}/>"#,
+                svg_fill(color)
+            );
+        }
+    }
+
+    fn fill_rect(&mut self, rect: Rect, color: Color) {
+        self.write_rect(rect.x, rect.y, rect.w, rect.h, color);
+    }
+
+    // Text stays text, in a monospace font stretched to the advance of the bundled font so that
+    // it lines up with everything laid out around it. The bundled font's capitals sit on its
+    // bottom row, which is where the baseline goes.
+    fn scaled_text(&mut self, x: f32, y: f32, scale: f32, text: &str, color: Color) {
+        let length = text.chars().count();
+        if self.result.is_ok() && length > 0 {
+            self.result = writeln!(
+                self.w,
+                r#"<text x="{}" y="{}" font-family="{}" font-size="{}" textLength="{}" lengthAdjust="spacingAndGlyphs" xml:space="preserve" {}>{}</text>"#,
+                x,
+                y + font::GLYPH_HEIGHT as f32 * scale,
+                FONT_FAMILY,
+                FONT_SIZE * scale,
+                (length as u32 * font::ADVANCE) as f32 * scale,
+                svg_fill(color),
+                escape(text)
+            );
+        }
+    }
+
+    fn read_pixels(&mut self) -> GameResult<Vec<u8>> {
+        Err(GameError::RenderError(
+            "SVG output has no pixels to read".to_string(),
+        ))
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+// The fill attributes for a color, with an opacity only for translucent colors so that they blend
+// like they do in the raster outputs.
+fn svg_fill(color: Color) -> String {
+    let (r, g, b) = color.to_rgb();
+    if color.a < 1.0 {
+        format!(
+            r##"fill="#{:02x}{:02x}{:02x}" fill-opacity="{}""##,
+            r,
+            g,
+            b,
+            color.a.max(0.0)
+        )
+    } else {
+        format!(r##"fill="#{:02x}{:02x}{:02x}""##, r, g, b)
+    }
+}
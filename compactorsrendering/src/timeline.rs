@@ -0,0 +1,112 @@
+use compactorsanim::{frame::Frame, stats::Stats, tracked::Tracked};
+use ggez::graphics::{Color, Rect};
+use renderingcommon::{
+    annotations::draw_legend,
+    font,
+    renderer::Renderer,
+    theme::{Layout, Theme},
+    Element,
+};
+
+use crate::annotations::LABEL_WIDTH;
+
+// A strip chart of how many items the sketch holds against how many it has seen, one column per
+// frame. It grows as the animation plays, showing that the space used grows far slower than the
+// stream.
+pub struct Timeline {
+    stats: Vec<Stats>,
+    // The largest value on the chart, which sits at its top.
+    max: usize,
+}
+
+impl Timeline {
+    pub fn new(frames: &[Frame<Tracked<Element>>]) -> Self {
+        let stats: Vec<Stats> = frames.iter().map(Frame::stats).collect();
+        let max = stats
+            .iter()
+            .map(|stats| stats.items_seen.max(stats.total_capacity))
+            .max()
+            .unwrap_or(0);
+        Self { stats, max }
+    }
+
+    fn chart_height(layout: &Layout) -> u32 {
+        3 * layout.max_element_height
+    }
+
+    pub fn height(layout: &Layout) -> u32 {
+        font::LINE_HEIGHT + Self::chart_height(layout) + 2 * layout.space_between_elements
+    }
+
+    // Draws the chart up to and including `frame` in the `width` pixels wide strip starting at
+    // `y`, with the current numbers in the label column.
+    pub fn render(
+        &self,
+        frame: usize,
+        theme: &Theme,
+        renderer: &mut dyn Renderer,
+        y: f32,
+        width: u32,
+    ) {
+        let layout = &theme.layout;
+        let space = layout.space_between_elements;
+        let current = self.stats[frame];
+        for (i, line) in [
+            format!("retained {} of {}", current.retained, current.items_seen),
+            format!(
+                "capacity {}, {} levels",
+                current.total_capacity, current.levels
+            ),
+            format!("{} compactions", current.compactions),
+        ]
+        .iter()
+        .enumerate()
+        {
+            renderer.text(
+                space as f32,
+                y + (i as u32 * font::LINE_HEIGHT) as f32,
+                line,
+                theme.text,
+            );
+        }
+
+        let left = (LABEL_WIDTH + space) as f32;
+        draw_legend(
+            renderer,
+            theme,
+            left,
+            y,
+            &[
+                ("items seen", theme.text),
+                ("capacity", theme.used_capacity),
+                ("retained", theme.element),
+            ],
+        );
+        let chart_height = Self::chart_height(layout) as f32;
+        let bottom = y + (font::LINE_HEIGHT + space) as f32 + chart_height;
+        let column_width =
+            width.saturating_sub(LABEL_WIDTH + 2 * space) as f32 / self.stats.len().max(1) as f32;
+        let height = |value: usize| value as f32 / self.max.max(1) as f32 * chart_height;
+        let bar = |i: usize, value: usize| {
+            let h = height(value);
+            Rect::new(left + i as f32 * column_width, bottom - h, column_width, h)
+        };
+        for (i, stats) in self.stats[..=frame].iter().enumerate() {
+            for (rect, color) in [
+                (bar(i, stats.total_capacity), theme.used_capacity),
+                (bar(i, stats.retained), theme.element),
+            ] {
+                renderer.fill_rect(rect, color);
+            }
+            let seen = bar(i, stats.items_seen);
+            renderer.fill_rect(Rect { h: 1.0, ..seen }, theme.text);
+        }
+        renderer.fill_rect(
+            Rect::new(left, bottom, column_width * self.stats.len() as f32, 1.0),
+            Color {
+                a: 0.5,
+                ..theme.text
+            },
+        );
+    }
+}
@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+
+use compactorsanim::{compactor::Compactor, frame::Frame, tracked::Tracked};
+use ggez::graphics::Color;
+use renderingcommon::{
+    easing::lerp_rect, renderer::Renderer, scale::ValueScale, theme::Theme, Element,
+};
+
+use crate::{
+    annotations, compactor_capacity_rects, compactor_element_to_rect, element_color, element_height,
+};
+
+#[derive(Clone, Copy, Debug)]
+pub enum DisplayFrame {
+    Frame(usize),
+    // Part way from frame `from` to the frame after it.
+    Tween { from: usize, t: f32 },
+}
+
+pub fn display_frames(frame_count: usize, tween_frames: u32) -> Vec<DisplayFrame> {
+    let mut display_frames = Vec::new();
+    for frame in 0..frame_count {
+        if frame > 0 {
+            for step in 1..=tween_frames {
+                display_frames.push(DisplayFrame::Tween {
+                    from: frame - 1,
+                    t: step as f32 / (tween_frames + 1) as f32,
+                });
+            }
+        }
+        display_frames.push(DisplayFrame::Frame(frame));
+    }
+    display_frames
+}
+
+// Elements that survive into `to` slide from their old position to their new one, elements that
+// were discarded fade out and new elements fade in.
+pub fn render(
+    from: &Frame<Tracked<Element>>,
+    to: &Frame<Tracked<Element>>,
+    t: f32,
+    theme: &Theme,
+    scale: &ValueScale,
+    renderer: &mut dyn Renderer,
+) {
+    let layout = &theme.layout;
+    renderer.clear(theme.background);
+    for (rect, color) in compactor_capacity_rects(&to.compactors, theme) {
+        renderer.fill_rect(rect, color);
+    }
+    let sources = match_elements(&from.compactors, &to.compactors);
+    let mut matched: Vec<Vec<bool>> = from
+        .compactors
+        .iter()
+        .map(|c| vec![false; c.data.len()])
+        .collect();
+    for (level, compactor) in to.compactors.iter().enumerate() {
+        for (index, element) in compactor.data.iter().enumerate() {
+            let rect = compactor_element_to_rect(
+                layout,
+                level,
+                index,
+                element_height(layout, scale, element),
+            );
+            match sources[level][index] {
+                Some((from_level, from_index)) => {
+                    matched[from_level][from_index] = true;
+                    let from_rect = compactor_element_to_rect(
+                        layout,
+                        from_level,
+                        from_index,
+                        element_height(
+                            layout,
+                            scale,
+                            &from.compactors[from_level].data[from_index],
+                        ),
+                    );
+                    renderer.fill_rect(
+                        lerp_rect(from_rect, rect, t),
+                        element_color(theme, to.event, level, index),
+                    );
+                }
+                None => renderer.fill_rect(
+                    rect,
+                    Color {
+                        a: t,
+                        ..element_color(theme, to.event, level, index)
+                    },
+                ),
+            }
+        }
+    }
+    for (level, compactor) in from.compactors.iter().enumerate() {
+        for (index, element) in compactor.data.iter().enumerate() {
+            if !matched[level][index] {
+                renderer.fill_rect(
+                    compactor_element_to_rect(
+                        layout,
+                        level,
+                        index,
+                        element_height(layout, scale, element),
+                    ),
+                    Color {
+                        a: 1.0 - t,
+                        ..element_color(theme, from.event, level, index)
+                    },
+                );
+            }
+        }
+    }
+    annotations::render(to, theme, renderer);
+}
+
+// Finds where each element of `to` was in `from`, following items by their id.
+fn match_elements(
+    from: &[Compactor<Tracked<Element>>],
+    to: &[Compactor<Tracked<Element>>],
+) -> Vec<Vec<Option<(usize, usize)>>> {
+    let positions: HashMap<usize, (usize, usize)> = from
+        .iter()
+        .enumerate()
+        .flat_map(|(level, compactor)| {
+            compactor
+                .data
+                .iter()
+                .enumerate()
+                .map(move |(index, element)| (element.id(), (level, index)))
+        })
+        .collect();
+    to.iter()
+        .map(|compactor| {
+            compactor
+                .data
+                .iter()
+                .map(|element| positions.get(&element.id()).copied())
+                .collect()
+        })
+        .collect()
+}
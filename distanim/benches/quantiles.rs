@@ -0,0 +1,40 @@
+use compactorsanim::{compactors::Compactors, frame::NoFrames};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use distanim::estimated_quantiles::EstimatedQuantiles;
+use rand::{prelude::StdRng, Rng, SeedableRng};
+
+const KS: [usize; 3] = [10, 100, 1000];
+const LENGTH: usize = 100_000;
+
+fn sketch(k: usize) -> Compactors<u32, NoFrames, true> {
+    let mut r = StdRng::seed_from_u64(42);
+    let mut compactors = Compactors::new(k, NoFrames);
+    for _ in 0..LENGTH {
+        compactors.update(r.gen());
+    }
+    compactors
+}
+
+// Turning a sketch into sorted weighted elements, then answering queries from them.
+fn queries(c: &mut Criterion) {
+    let mut group = c.benchmark_group("query");
+    for k in KS {
+        let compactors = sketch(k);
+        group.bench_with_input(
+            BenchmarkId::new("estimate", k),
+            &compactors,
+            |b, compactors| b.iter(|| EstimatedQuantiles::from(compactors)),
+        );
+        let est = EstimatedQuantiles::from(&compactors);
+        group.bench_with_input(BenchmarkId::new("quantile", k), &est, |b, est| {
+            b.iter(|| est.quantile(0.5).copied())
+        });
+        group.bench_with_input(BenchmarkId::new("rank", k), &est, |b, est| {
+            b.iter(|| est.rank(&(u32::MAX / 2)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, queries);
+criterion_main!(benches);
@@ -0,0 +1,51 @@
+use crate::estimated_quantiles::EstimatedQuantiles;
+
+pub const DECILES: [f64; 9] = [0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.9];
+
+// How far the estimates are from the exact answers over the items the sketch has seen. Errors are
+// in ranks, so an error of 3 means the estimate is 3 items away from the right answer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RankErrors {
+    // The largest difference between the estimated and true rank of any item seen.
+    pub max_rank_error: usize,
+    // For each of the quantiles measured, how far the true rank of the estimated quantile is from
+    // the rank that was asked for.
+    pub quantile_errors: Vec<usize>,
+}
+
+impl RankErrors {
+    // `seen` must be every item the sketch has seen, sorted.
+    pub fn measure<T: Ord>(est: &EstimatedQuantiles<T>, seen: &[T], quantiles: &[f64]) -> Self {
+        let true_rank = |x: &T| seen.partition_point(|y| y <= x);
+        // Walks both in order, so that long streams take linear rather than quadratic time.
+        let mut max_rank_error = 0;
+        let mut estimated_rank = 0;
+        let mut elements = est.elements.iter().peekable();
+        for (i, x) in seen.iter().enumerate() {
+            if seen.get(i + 1) == Some(x) {
+                continue;
+            }
+            while let Some(element) = elements.next_if(|e| e.element <= *x) {
+                estimated_rank += element.weight;
+            }
+            max_rank_error = max_rank_error.max(estimated_rank.abs_diff(i + 1));
+        }
+        let n = seen.len();
+        let quantile_errors = quantiles
+            .iter()
+            .map(|&q| match est.quantile(q) {
+                Some(x) => {
+                    // Equal items share every rank between these two.
+                    let (first, last) = (seen.partition_point(|y| y < x) + 1, true_rank(x));
+                    let target = (q.clamp(0.0, 1.0) * n as f64).ceil() as usize;
+                    first.saturating_sub(target) + target.saturating_sub(last)
+                }
+                None => 0,
+            })
+            .collect();
+        Self {
+            max_rank_error,
+            quantile_errors,
+        }
+    }
+}
@@ -1,8 +1,8 @@
 use std::fmt::Debug;
 
-use compactorsanim::{compactor::Compactor, compactors::Compactors};
+use compactorsanim::{compactor::Compactor, compactors::Compactors, frame::FrameHandler};
 
-#[derive(PartialEq, Eq, Debug)]
+#[derive(Clone, PartialEq, Eq, Debug)]
 pub struct QuantileElement<T> {
     pub weight: usize,
     pub element: T,
@@ -28,21 +28,41 @@ where
 
 pub struct EstimatedQuantiles<T> {
     pub elements: Vec<QuantileElement<T>>,
+    // No estimated rank is further than this from the true rank.
+    pub rank_error_bound: usize,
 }
 
 impl<T> EstimatedQuantiles<T> {
     pub fn estimated_element_count(&self) -> usize {
         self.elements.iter().map(|e| e.weight).sum()
     }
+
+    // The smallest element whose cumulative weight reaches q of the total, so 0.5 is the median.
+    pub fn quantile(&self, q: f64) -> Option<&T> {
+        let target = (q.clamp(0.0, 1.0) * self.estimated_element_count() as f64).ceil() as usize;
+        let mut cumulative_weight = 0;
+        self.elements
+            .iter()
+            .find(|e| {
+                cumulative_weight += e.weight;
+                cumulative_weight >= target
+            })
+            .map(|e| &e.element)
+    }
 }
 
-impl<T, F, const LAZY: bool> From<Compactors<T, F, LAZY>> for EstimatedQuantiles<T>
-where
-    T: Ord + Debug,
-    F: FnMut(Vec<Compactor<T>>),
-{
-    fn from(compactors: Compactors<T, F, LAZY>) -> Self {
-        let mut elements: Vec<QuantileElement<T>> = Into::<Vec<Compactor<T>>>::into(compactors)
+impl<T: Ord> EstimatedQuantiles<T> {
+    // The estimated number of items less than or equal to x.
+    pub fn rank(&self, x: &T) -> usize {
+        self.elements
+            .iter()
+            .take_while(|e| e.element <= *x)
+            .map(|e| e.weight)
+            .sum()
+    }
+
+    fn from_levels(levels: Vec<Compactor<T>>, rank_error_bound: usize) -> Self {
+        let mut elements: Vec<QuantileElement<T>> = levels
             .into_iter()
             .enumerate()
             .flat_map(|(level, compactor)| {
@@ -50,12 +70,37 @@ where
                     .data
                     .into_iter()
                     .map(move |element| QuantileElement {
-                        weight: 1 << level.clone(),
+                        weight: 1 << level,
                         element,
                     })
             })
             .collect();
         elements.sort();
-        Self { elements }
+        Self {
+            elements,
+            rank_error_bound,
+        }
+    }
+}
+
+impl<T, F, const LAZY: bool> From<Compactors<T, F, LAZY>> for EstimatedQuantiles<T>
+where
+    T: Ord + Debug,
+    F: FrameHandler<T>,
+{
+    fn from(compactors: Compactors<T, F, LAZY>) -> Self {
+        let rank_error_bound = compactors.rank_error_bound();
+        Self::from_levels(compactors.into(), rank_error_bound)
+    }
+}
+
+// Estimates from the sketch so far, so that it can keep taking updates.
+impl<T, F, const LAZY: bool> From<&Compactors<T, F, LAZY>> for EstimatedQuantiles<T>
+where
+    T: Ord + Clone + Debug,
+    F: FrameHandler<T>,
+{
+    fn from(compactors: &Compactors<T, F, LAZY>) -> Self {
+        Self::from_levels(compactors.snapshot(), compactors.rank_error_bound())
     }
 }
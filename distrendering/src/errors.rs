@@ -0,0 +1,232 @@
+use std::{cmp::max, time::Duration};
+
+use compactorsanim::{compactors::Compactors, frame::NoFrames};
+use distanim::{
+    error::{RankErrors, DECILES},
+    estimated_quantiles::EstimatedQuantiles,
+};
+use ggez::graphics::{Color, Rect};
+use renderingcommon::{
+    annotations::{draw_header, header_height, legend_width},
+    easing::lerp_color,
+    font,
+    renderer::Renderer,
+    scale::ValueScale,
+    theme::{Layout, Theme},
+    Element,
+};
+
+use crate::color;
+
+// The estimate after every item of the stream, with how far it is from the exact answers over the
+// items seen so far. Frame i shows the sketch after i + 1 items and the errors up to then.
+pub struct ErrorAnimation {
+    steps: Vec<Step>,
+    // The largest error of any step, which sits at the top of the chart.
+    max_error: usize,
+}
+
+struct Step {
+    est: EstimatedQuantiles<Element>,
+    errors: RankErrors,
+}
+
+impl ErrorAnimation {
+    pub fn new<const LAZY: bool>(samples: &[Element]) -> Self {
+        let mut compactors: Compactors<Element, _, LAZY> = Compactors::new(10, NoFrames);
+        let mut seen: Vec<Element> = Vec::new();
+        let steps: Vec<Step> = samples
+            .iter()
+            .map(|&sample| {
+                compactors.update(sample);
+                seen.insert(seen.partition_point(|&x| x <= sample), sample);
+                let est = EstimatedQuantiles::from(&compactors);
+                let errors = RankErrors::measure(&est, &seen, &DECILES);
+                Step { est, errors }
+            })
+            .collect();
+        let max_error = steps
+            .iter()
+            .flat_map(|step| {
+                std::iter::once(step.errors.max_rank_error)
+                    .chain(step.errors.quantile_errors.iter().copied())
+            })
+            .max()
+            .unwrap_or(0);
+        Self { steps, max_error }
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.steps.len()
+    }
+
+    fn chart_height(layout: &Layout) -> u32 {
+        4 * layout.max_element_height
+    }
+
+    fn column_width(layout: &Layout) -> u32 {
+        layout.element_width + layout.space_between_elements
+    }
+
+    fn elements_height(&self, layout: &Layout) -> u32 {
+        let tallest = self
+            .steps
+            .iter()
+            .flat_map(|step| step.est.elements.iter().map(|e| e.weight))
+            .max()
+            .unwrap_or(0) as u32;
+        max(tallest, layout.max_element_height)
+    }
+
+    // Wide enough for the largest sketch, and for two pixels per item of the stream.
+    fn chart_width(&self, layout: &Layout) -> u32 {
+        let widest = self
+            .steps
+            .iter()
+            .map(|step| step.est.elements.len())
+            .max()
+            .unwrap_or(0) as u32;
+        max(
+            widest * Self::column_width(layout),
+            2 * self.steps.len() as u32,
+        )
+    }
+
+    pub fn size(&self, theme: &Theme) -> (u32, u32) {
+        let layout = &theme.layout;
+        let space = layout.space_between_elements;
+        let text_width = (0..self.frame_count())
+            .flat_map(|frame| self.text(frame))
+            .map(|line| font::text_width(&line))
+            .max()
+            .unwrap_or(0);
+        let width = max(
+            self.chart_width(layout),
+            max(text_width, legend_width(&legend(theme))),
+        );
+        (
+            width + 2 * space,
+            header_height(layout)
+                + self.elements_height(layout)
+                + Self::chart_height(layout)
+                + 2 * font::LINE_HEIGHT
+                + 4 * space,
+        )
+    }
+
+    fn text(&self, frame: usize) -> [String; 2] {
+        let step = &self.steps[frame];
+        let n = frame + 1;
+        [
+            format!("How far off is the sketch after {} items?", n),
+            format!(
+                "largest rank error {} ({:.1}%), guaranteed at most {}",
+                step.errors.max_rank_error,
+                step.errors.max_rank_error as f64 / n as f64 * 100.0,
+                step.est.rank_error_bound
+            ),
+        ]
+    }
+
+    pub fn render(
+        &self,
+        frame: usize,
+        theme: &Theme,
+        scale: &ValueScale,
+        renderer: &mut dyn Renderer,
+        delay: Duration,
+    ) -> Duration {
+        let layout = &theme.layout;
+        let space = layout.space_between_elements;
+        renderer.clear(theme.background);
+        let x = space as f32;
+        let [caption, errors] = self.text(frame);
+        draw_header(renderer, theme, [&caption, &errors], &legend(theme));
+
+        let elements_y = (header_height(layout) + space) as f32;
+        let column_width = Self::column_width(layout) as f32;
+        for (i, element) in self.steps[frame].est.elements.iter().enumerate() {
+            renderer.fill_rect(
+                Rect::new(
+                    x + i as f32 * column_width,
+                    elements_y,
+                    layout.element_width as f32,
+                    element.weight as f32,
+                ),
+                color(theme, scale, element.element),
+            );
+        }
+
+        let title_y = elements_y + (self.elements_height(layout) + space) as f32;
+        renderer.text(
+            x,
+            title_y,
+            &format!("rank error, up to {}", self.max_error),
+            theme.text,
+        );
+        let chart_y = title_y + font::LINE_HEIGHT as f32;
+        let chart_height = Self::chart_height(layout) as f32;
+        let chart_width = self.chart_width(layout) as f32;
+        let step_width = chart_width / self.steps.len() as f32;
+        let y = |error: usize| {
+            chart_y + chart_height - error as f32 / self.max_error.max(1) as f32 * chart_height
+        };
+        // Joins each step to the one before it with a vertical segment, so the lines are unbroken.
+        let mut plot = |error: &dyn Fn(&Step) -> usize, color: Color| {
+            for i in 0..=frame {
+                let current = y(error(&self.steps[i]));
+                let previous = if i == 0 {
+                    current
+                } else {
+                    y(error(&self.steps[i - 1]))
+                };
+                let left = x + i as f32 * step_width;
+                renderer.fill_rect(
+                    Rect::new(
+                        left,
+                        previous.min(current),
+                        1.0,
+                        (previous - current).abs() + 1.0,
+                    ),
+                    color,
+                );
+                renderer.fill_rect(Rect::new(left, current, step_width, 1.0), color);
+            }
+        };
+        for (decile, q) in DECILES.iter().enumerate() {
+            plot(
+                &|step| step.errors.quantile_errors[decile],
+                decile_color(theme, *q as f32),
+            );
+        }
+        plot(&|step| step.errors.max_rank_error, theme.text);
+
+        let axis_y = chart_y + chart_height + 1.0;
+        let last = self.steps.len().to_string();
+        renderer.text(x, axis_y, "1", theme.text);
+        renderer.text(
+            x + chart_width - font::text_width(&last) as f32,
+            axis_y,
+            &last,
+            theme.text,
+        );
+        if frame + 1 == self.steps.len() {
+            delay * 8
+        } else {
+            delay
+        }
+    }
+}
+
+// From the 10% quantile to the 90% quantile.
+fn decile_color(theme: &Theme, q: f32) -> Color {
+    lerp_color(theme.pdf, theme.cdf, (q - 0.1) / 0.8)
+}
+
+fn legend(theme: &Theme) -> [(&'static str, Color); 3] {
+    [
+        ("largest rank error", theme.text),
+        ("10% quantile", decile_color(theme, 0.1)),
+        ("90% quantile", decile_color(theme, 0.9)),
+    ]
+}
@@ -1,240 +1,252 @@
-use std::{cmp::max, fs::File};
+mod errors;
+mod overlay;
+mod query;
 
-use compactorsanim::compactors::Compactors;
+use std::{cmp::max, time::Duration};
+
+use compactorsanim::{compactors::Compactors, frame::NoFrames};
 use distanim::estimated_quantiles::{EstimatedQuantiles, QuantileElement};
 use ggez::{
-    conf::{NumSamples, WindowMode},
-    event::{self, EventHandler},
-    graphics::{
-        self, get_window_color_format, Canvas, Color, DrawMode, DrawParam, Mesh, MeshBuilder, Rect,
-        Transform,
-    },
-    mint::{self, Point2},
-    Context, GameError, GameResult,
+    graphics::{Color, Rect},
+    GameResult,
 };
 
-use png::{BitDepth, ColorType};
 use rand::{prelude::StdRng, SeedableRng};
 use rand_distr::Distribution;
 use renderingcommon::{
-    Element, DISTRIBUTION, DISTRIBUTION_MIN, DISTRIBUTION_PRACTICAL_RANGE, ELEMENT_WIDTH,
-    MAX_ELEMENT_HEIGHT, SPACE_BETWEEN_ELEMENTS,
+    annotations::{draw_header, header_height, header_width},
+    font,
+    options::{Options, Program},
+    renderer::{render_frames, Renderer},
+    scale::ValueScale,
+    theme::{Layout, Theme},
+    viewer,
+    viewport::Viewport,
+    Element, DISTRIBUTION,
 };
 
+use errors::ErrorAnimation;
+use overlay::{quantile_bucket, Overlay};
+use query::QueryAnimation;
+
+#[derive(Clone)]
 struct MainState<const BUCKETS: usize> {
     elements: Vec<QuantileElement<Element>>,
     estimated_element_count: usize,
     elements_seen: usize,
+    last_bucket: Option<usize>,
     buckets: [Vec<QuantileElement<Element>>; BUCKETS],
 }
 
-fn color(element: Element) -> Color {
-    let value: f32 = (element as f32 - *DISTRIBUTION_MIN) / *DISTRIBUTION_PRACTICAL_RANGE;
-    Color {
-        r: value,
-        g: value,
-        b: value,
-        a: 1.0,
-    }
+fn color(theme: &Theme, scale: &ValueScale, element: Element) -> Color {
+    theme.value_color(scale.fraction(element as f32))
 }
 
 impl<const BUCKETS: usize> MainState<BUCKETS> {
-    fn elements_size(&self) -> (u32, u32) {
+    fn elements_size(&self, layout: &Layout) -> (u32, u32) {
         (
-            self.elements.len() as u32 * (ELEMENT_WIDTH + SPACE_BETWEEN_ELEMENTS) as u32,
-            MAX_ELEMENT_HEIGHT as u32,
+            self.elements.len() as u32 * (layout.element_width + layout.space_between_elements),
+            layout.max_element_height,
+        )
+    }
+    fn header_size(&self, theme: &Theme) -> (u32, u32) {
+        (
+            header_width(theme, [&self.caption(), &self.items_seen()], &legend(theme)),
+            header_height(&theme.layout),
+        )
+    }
+    fn caption(&self) -> String {
+        match self.last_bucket {
+            None => "Weighted items from the sketch, in sorted order".to_string(),
+            Some(bucket) => format!("Added the next item to bucket {}", bucket),
+        }
+    }
+    fn items_seen(&self) -> String {
+        format!(
+            "items seen: {}/{}, height is weight",
+            self.elements_seen, self.estimated_element_count
         )
     }
-    fn buckets_size(&self) -> (u32, u32) {
+    fn buckets_size(&self, layout: &Layout) -> (u32, u32) {
         (
-            BUCKETS as u32 * (ELEMENT_WIDTH + SPACE_BETWEEN_ELEMENTS),
+            BUCKETS as u32 * (layout.element_width + layout.space_between_elements),
             self.elements.iter().map(|e| e.weight).sum::<usize>() as u32,
         )
     }
     fn step(&mut self) {
         if !self.elements.is_empty() {
             let current_element = self.elements.remove(0);
-            let current_bucket = self.elements_seen / (self.estimated_element_count / BUCKETS);
-            println!(
-                "{}/({}/{}) = {}",
-                self.elements_seen, self.estimated_element_count, BUCKETS, current_bucket
-            );
-            let current_bucket = if current_bucket == BUCKETS {
-                current_bucket - 1
-            } else {
-                current_bucket
-            };
+            let current_bucket =
+                quantile_bucket::<BUCKETS>(self.elements_seen, self.estimated_element_count);
             self.elements_seen += current_element.weight;
+            self.last_bucket = Some(current_bucket);
             self.buckets[current_bucket].push(current_element);
         }
-        println!("{:?}", self.buckets);
-        println!("{:?}", self.elements);
     }
-    fn render_elements(&self, ctx: &mut Context) -> Result<Option<Mesh>, GameError> {
-        if self.elements.is_empty() {
-            Ok(None)
-        } else {
-            let mut mesh_builder = MeshBuilder::new();
-            for (i, element) in self.elements.iter().enumerate() {
-                mesh_builder.rectangle(
-                    DrawMode::fill(),
+    fn render(
+        &self,
+        theme: &Theme,
+        scale: &ValueScale,
+        overlay: &Overlay<BUCKETS>,
+        renderer: &mut dyn Renderer,
+    ) {
+        let layout = &theme.layout;
+        let (space, element_width) = (layout.space_between_elements, layout.element_width);
+        renderer.clear(theme.background);
+        let (x, y) = (space as i32, (header_height(layout) + space) as i32);
+        self.render_header(theme, renderer);
+        for (i, element) in self.elements.iter().enumerate() {
+            renderer.fill_rect(
+                Rect::new_i32(
+                    x + (i as u32 * (element_width + space)) as i32,
+                    y,
+                    element_width as i32,
+                    element.weight as i32,
+                ),
+                color(theme, scale, element.element),
+            );
+        }
+        let label_y = y + (space + layout.max_element_height) as i32;
+        let y = label_y + font::LINE_HEIGHT as i32;
+        for (i, bucket) in self.buckets.iter().enumerate() {
+            renderer.text(
+                (x + (i as u32 * (element_width + space)) as i32) as f32,
+                label_y as f32,
+                &i.to_string(),
+                theme.text,
+            );
+            let mut bucket_y = y;
+            for element in bucket {
+                renderer.fill_rect(
                     Rect::new_i32(
-                        (i as u32 * (ELEMENT_WIDTH + SPACE_BETWEEN_ELEMENTS)) as i32,
-                        0,
-                        ELEMENT_WIDTH as i32,
+                        x + (i as u32 * (element_width + space)) as i32,
+                        bucket_y,
+                        element_width as i32,
                         element.weight as i32,
                     ),
-                    color(element.element),
-                )?;
+                    color(theme, scale, element.element),
+                );
+                bucket_y += element.weight as i32;
             }
-            mesh_builder.build(ctx).map(Some)
         }
+        overlay.render(
+            theme,
+            renderer,
+            x as f32,
+            y as f32,
+            self.estimated_element_count as f32,
+        );
     }
-    fn render_bucket(&self, ctx: &mut Context) -> Result<Option<Mesh>, GameError> {
-        if self.buckets.iter().all(Vec::is_empty) {
-            Ok(None)
-        } else {
-            let mut mesh_builder = MeshBuilder::new();
-            for (i, bucket) in self.buckets.iter().enumerate() {
-                let mut y = 0;
-                for element in bucket {
-                    mesh_builder.rectangle(
-                        DrawMode::fill(),
-                        Rect::new_i32(
-                            (i as u32 * (ELEMENT_WIDTH + SPACE_BETWEEN_ELEMENTS)) as i32,
-                            y as i32,
-                            ELEMENT_WIDTH as i32,
-                            element.weight as i32,
-                        ),
-                        color(element.element),
-                    )?;
-                    y += element.weight;
-                }
-            }
-            mesh_builder.build(ctx).map(Some)
-        }
+    fn render_header(&self, theme: &Theme, renderer: &mut dyn Renderer) {
+        draw_header(
+            renderer,
+            theme,
+            [&self.caption(), &self.items_seen()],
+            &legend(theme),
+        );
     }
 }
 
-impl<const BUCKETS: usize> EventHandler<GameError> for MainState<BUCKETS> {
-    fn update(&mut self, _ctx: &mut ggez::Context) -> Result<(), GameError> {
-        Ok(())
-    }
-
-    fn draw(&mut self, ctx: &mut ggez::Context) -> Result<(), GameError> {
-        graphics::clear(ctx, Color::BLACK);
-        if let Some(mesh) = self.render_elements(ctx)? {
-            graphics::draw(
-                ctx,
-                &mesh,
-                DrawParam::default().transform(
-                    Transform::Values {
-                        dest: mint::Point2 { x: 0.0, y: 0.0 },
-                        rotation: 0.0,
-                        scale: mint::Vector2 { x: 1.0, y: 1.0 },
-                        offset: mint::Point2 {
-                            x: -(SPACE_BETWEEN_ELEMENTS as f32),
-                            y: -(SPACE_BETWEEN_ELEMENTS as f32),
-                        },
-                    }
-                    .to_bare_matrix(),
-                ),
-            )?;
-        }
-        if let Some(mesh) = self.render_bucket(ctx)? {
-            graphics::draw(
-                ctx,
-                &mesh,
-                DrawParam::default().transform(
-                    Transform::Values {
-                        dest: mint::Point2 { x: 0.0, y: 0.0 },
-                        rotation: 0.0,
-                        scale: mint::Vector2 { x: 1.0, y: 1.0 },
-                        offset: mint::Point2 {
-                            x: -(SPACE_BETWEEN_ELEMENTS as f32),
-                            y: -((SPACE_BETWEEN_ELEMENTS * 2 + MAX_ELEMENT_HEIGHT) as f32),
-                        },
-                    }
-                    .to_bare_matrix(),
-                ),
-            )?;
-        }
-        graphics::present(ctx)
-    }
-
-    fn key_down_event(
-        &mut self,
-        ctx: &mut ggez::Context,
-        keycode: ggez::event::KeyCode,
-        _keymods: ggez::event::KeyMods,
-        _repeat: bool,
-    ) {
-        if keycode == ggez::event::KeyCode::Escape {
-            return ggez::event::quit(ctx);
-        }
-        self.step();
-    }
+fn legend(theme: &Theme) -> [(&'static str, Color); 5] {
+    [
+        ("small value", theme.low_value),
+        ("large value", theme.high_value),
+        ("true pdf", theme.pdf),
+        ("true cdf", theme.cdf),
+        ("samples", theme.histogram),
+    ]
 }
 
-fn make_est<D: Distribution<f32>, const LAZY: bool>(d: D) -> EstimatedQuantiles<Element> {
-    let mut compactors: Compactors<Element, _, LAZY> = Compactors::new(10, |_| {});
+// Also returns every raw sample, for comparison with the estimate.
+fn make_est<D: Distribution<f32>, const LAZY: bool>(
+    d: D,
+) -> (EstimatedQuantiles<Element>, Vec<Element>) {
+    let mut compactors: Compactors<Element, _, LAZY> = Compactors::new(10, NoFrames);
+    let mut samples = Vec::new();
     let mut r = StdRng::seed_from_u64(42);
     for _i in 1..100 {
-        compactors.update(d.sample(&mut r) as u32);
+        let sample = d.sample(&mut r) as u32;
+        samples.push(sample);
+        compactors.update(sample);
     }
-    compactors.into()
+    (compactors.into(), samples)
 }
 
 fn main() -> GameResult {
-    let est = make_est::<_, true>(*DISTRIBUTION);
-    println!("{:?},{}", est.elements, est.elements.len());
-    let (mut ctx, _event_loop) = ggez::ContextBuilder::new("quantiles", "jedmonds").build()?;
+    let options = Options::from_env(Program::Distribution);
+    let (est, samples) = make_est::<_, true>(*DISTRIBUTION);
+    let overlay: Overlay<7> = Overlay::new(&samples);
+    let theme = options.theme.clone();
+    let scale = ValueScale::new(
+        options.scale,
+        options.scale_range,
+        est.elements.iter().map(|element| element.element as f32),
+    );
+    if options.errors {
+        let animation = ErrorAnimation::new::<true>(&samples);
+        let (w, h) = animation.size(&theme);
+        let viewport = Viewport::fit(w, h, &options.viewport)?;
+        let delay = options.output.frame_delay;
+        let frame_count = animation.frame_count();
+        return show(&options, &viewport, frame_count, move |i, renderer| {
+            animation.render(i, &theme, &scale, &mut viewport.apply(renderer), delay)
+        });
+    }
+    if let Some(query) = options.query {
+        let animation = QueryAnimation::new(est, query);
+        let (w, h) = animation.size(&theme);
+        let viewport = Viewport::fit(w, h, &options.viewport)?;
+        let delay = options.output.frame_delay;
+        let frame_count = animation.frame_count();
+        return show(&options, &viewport, frame_count, move |i, renderer| {
+            animation.render(i, &theme, &scale, &mut viewport.apply(renderer), delay)
+        });
+    }
     let mut state: MainState<7> = MainState {
         buckets: Default::default(),
         estimated_element_count: est.estimated_element_count(),
         elements: est.elements,
         elements_seen: 0,
+        last_bucket: None,
     };
-    let (elements_width, elements_height) = state.elements_size();
-    let (bucket_width, bucket_height) = state.buckets_size();
+    let space = theme.layout.space_between_elements;
+    let (elements_width, elements_height) = state.elements_size(&theme.layout);
+    let (bucket_width, bucket_height) = state.buckets_size(&theme.layout);
+    let (header_width, header_height) = state.header_size(&theme);
+    // The bucket labels sit between the elements and the buckets, and the overlay's smallest and
+    // largest sample below the buckets.
     let (w, h) = (
-        max(elements_width, bucket_width),
-        elements_height + bucket_height,
+        max(max(elements_width, bucket_width), header_width) + space,
+        header_height + elements_height + bucket_height + 2 * space + 2 * font::LINE_HEIGHT,
     );
 
-    let mut mode = WindowMode::default();
-    mode.width = w as f32;
-    mode.height = h as f32;
-    println!("{} {}", elements_width, elements_height);
-    graphics::set_mode(&mut ctx, mode)?;
-    graphics::set_screen_coordinates(&mut ctx, Rect::new_i32(0, 0, w as i32, h as i32))?;
-    // event::run(ctx, _event_loop, state)
-
-    let color_format = get_window_color_format(&ctx);
-    let canvas = Canvas::new(&mut ctx, w as u16, h as u16, NumSamples::One, color_format)?;
-    graphics::set_canvas(&mut ctx, Some(&canvas));
-    let mut frame_id = 0;
-    for i in 0..state.elements.len() {
-        graphics::set_canvas(&mut ctx, Some(&canvas));
-        state.draw(&mut ctx)?;
-        save_frame(canvas.to_rgba8(&mut ctx)?, frame_id, w, h);
-        frame_id += 1;
+    let mut frames = vec![state.clone()];
+    while !state.elements.is_empty() {
         state.step();
+        frames.push(state.clone());
     }
-    Ok(())
+    let frame_count = frames.len();
+    let delay = options.output.frame_delay;
+    let viewport = Viewport::fit(w, h, &options.viewport)?;
+    show(&options, &viewport, frame_count, move |i, renderer| {
+        frames[i].render(&theme, &scale, &overlay, &mut viewport.apply(renderer));
+        delay
+    })
 }
 
-fn save_frame(frame: Vec<u8>, frame_id: u32, width: u32, height: u32) {
-    let mut encoder = png::Encoder::new(
-        File::create(format!("frame{:0w$}.png", frame_id, w = 4)).unwrap(),
-        width,
-        height,
-    );
-    encoder.set_color(ColorType::Rgba);
-    encoder.set_depth(BitDepth::Eight);
-    encoder
-        .write_header()
-        .unwrap()
-        .write_image_data(&frame)
-        .unwrap();
+fn show<F>(
+    options: &Options,
+    viewport: &Viewport,
+    frame_count: usize,
+    render_frame: F,
+) -> GameResult
+where
+    F: FnMut(usize, &mut dyn Renderer) -> Duration + 'static,
+{
+    let (w, h) = (viewport.width, viewport.height);
+    if options.interactive {
+        viewer::run(w, h, frame_count, render_frame)
+    } else {
+        render_frames(options, w, h, frame_count, render_frame)
+    }
 }
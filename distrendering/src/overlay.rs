@@ -0,0 +1,110 @@
+use std::ops::Range;
+
+use ggez::graphics::Rect;
+use renderingcommon::{
+    distribution::normal_cdf, font, renderer::Renderer, theme::Theme, Element, DISTRIBUTION,
+};
+
+// The bucket for the item after `seen` of `count` weight, so that every bucket gets an equal share
+// of the ranks and the last one takes what is left over.
+pub fn quantile_bucket<const BUCKETS: usize>(seen: usize, count: usize) -> usize {
+    (seen / (count / BUCKETS).max(1)).min(BUCKETS - 1)
+}
+
+// What the buckets should look like, on the same quantile axis as the buckets: each column of the
+// bucket area is a range of ranks, and the raw samples are split into the same ranges. The
+// histogram counts the samples in each range. The pdf, integrated over the values a range spans,
+// is how many samples the true density predicts there, so it matches the histogram where the
+// samples fit the source. The cdf is the true distribution function at each sample quantile, which
+// runs along the diagonal for a good fit.
+pub struct Overlay<const BUCKETS: usize> {
+    sorted: Vec<Element>,
+}
+
+impl<const BUCKETS: usize> Overlay<BUCKETS> {
+    pub fn new(samples: &[Element]) -> Self {
+        let mut sorted = samples.to_vec();
+        sorted.sort_unstable();
+        Self { sorted }
+    }
+
+    // The ranks of the samples in `bucket`.
+    fn ranks(&self, bucket: usize) -> Range<usize> {
+        let count = self.sorted.len();
+        let start = (0..count)
+            .find(|&rank| quantile_bucket::<BUCKETS>(rank, count) >= bucket)
+            .unwrap_or(count);
+        let end = (start..count)
+            .find(|&rank| quantile_bucket::<BUCKETS>(rank, count) > bucket)
+            .unwrap_or(count);
+        start..end
+    }
+
+    // The values the samples in `ranks` span, up to where the next range starts. Samples are whole
+    // numbers, so the largest one covers up to the next integer.
+    fn values(&self, ranks: &Range<usize>) -> Range<f32> {
+        let end = match self.sorted.get(ranks.end) {
+            Some(&next) => next,
+            None => self.sorted[self.sorted.len() - 1] + 1,
+        };
+        self.sorted[ranks.start] as f32..end as f32
+    }
+
+    pub fn render(&self, theme: &Theme, renderer: &mut dyn Renderer, x: f32, y: f32, height: f32) {
+        let (first, last) = match (self.sorted.first(), self.sorted.last()) {
+            (Some(&first), Some(&last)) => (first, last),
+            _ => return,
+        };
+        let layout = &theme.layout;
+        let column_width = layout.element_width + layout.space_between_elements;
+        let element_width = layout.element_width as f32;
+        for bucket in 0..BUCKETS {
+            let ranks = self.ranks(bucket);
+            if ranks.is_empty() {
+                continue;
+            }
+            let (left, count) = (
+                x + (bucket as u32 * column_width) as f32,
+                ranks.len() as f32,
+            );
+            for edge in [
+                Rect::new(left, y, 1.0, count),
+                Rect::new(left + element_width - 1.0, y, 1.0, count),
+                Rect::new(left, y + count - 1.0, element_width, 1.0),
+            ] {
+                renderer.fill_rect(edge, theme.histogram);
+            }
+            let values = self.values(&ranks);
+            let expected_count = self.sorted.len() as f32
+                * (normal_cdf(&DISTRIBUTION, values.end) - normal_cdf(&DISTRIBUTION, values.start));
+            renderer.fill_rect(
+                Rect::new(left, y + expected_count, element_width, 1.0),
+                theme.pdf,
+            );
+            for offset in 0..column_width {
+                let rank =
+                    ranks.start + ((offset as f32 + 0.5) / column_width as f32 * count) as usize;
+                let value = self.sorted[rank.min(ranks.end - 1)] as f32 + 0.5;
+                let column = left + offset as f32;
+                renderer.fill_rect(
+                    Rect::new(
+                        column,
+                        y + height * normal_cdf(&DISTRIBUTION, value),
+                        1.0,
+                        1.0,
+                    ),
+                    theme.cdf,
+                );
+            }
+        }
+        let width = (column_width * BUCKETS as u32) as f32;
+        let last_label = last.to_string();
+        renderer.text(x, y + height, &first.to_string(), theme.text);
+        renderer.text(
+            x + width - font::text_width(&last_label) as f32,
+            y + height,
+            &last_label,
+            theme.text,
+        );
+    }
+}
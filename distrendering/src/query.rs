@@ -0,0 +1,219 @@
+use std::{cmp::max, time::Duration};
+
+use distanim::estimated_quantiles::EstimatedQuantiles;
+use ggez::graphics::{Color, Rect};
+use renderingcommon::{
+    annotations::{draw_header, header_height, legend_width},
+    font,
+    options::Query,
+    renderer::Renderer,
+    scale::ValueScale,
+    theme::{Layout, Theme},
+    Element,
+};
+
+use crate::color;
+
+// Walks the sorted weighted elements from the left, adding up their weights until the query is
+// answered. Frame i shows the first i elements accumulated, and the last frame the answer.
+pub struct QueryAnimation {
+    est: EstimatedQuantiles<Element>,
+    query: Query,
+    // How many elements are accumulated before the query is answered.
+    steps: usize,
+}
+
+impl QueryAnimation {
+    pub fn new(est: EstimatedQuantiles<Element>, query: Query) -> Self {
+        let steps = match query {
+            Query::Quantile(_) => {
+                let target = target_weight(&est, query);
+                let mut cumulative_weight = 0;
+                est.elements
+                    .iter()
+                    .position(|e| {
+                        cumulative_weight += e.weight;
+                        cumulative_weight >= target
+                    })
+                    .map_or(est.elements.len(), |i| i + 1)
+            }
+            Query::Rank(x) => est.elements.iter().take_while(|e| e.element <= x).count(),
+        };
+        Self { est, query, steps }
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.steps + 1
+    }
+
+    fn chart_height(layout: &Layout) -> u32 {
+        4 * layout.max_element_height
+    }
+
+    pub fn size(&self, theme: &Theme) -> (u32, u32) {
+        let layout = &theme.layout;
+        let space = layout.space_between_elements;
+        let text_width = (0..self.frame_count())
+            .flat_map(|frame| self.text(frame))
+            .map(|line| font::text_width(&line))
+            .max()
+            .unwrap_or(0);
+        let width = max(
+            self.est.elements.len() as u32 * (layout.element_width + space),
+            max(text_width, legend_width(&self.legend(theme))),
+        );
+        let tallest = self
+            .est
+            .elements
+            .iter()
+            .map(|e| e.weight)
+            .max()
+            .unwrap_or(0) as u32;
+        (
+            width + 2 * space,
+            header_height(layout)
+                + max(tallest, layout.max_element_height)
+                + Self::chart_height(layout)
+                + font::LINE_HEIGHT
+                + 4 * space,
+        )
+    }
+
+    fn legend(&self, theme: &Theme) -> Vec<(&'static str, Color)> {
+        match self.query {
+            Query::Quantile(_) => vec![("cumulative weight", theme.element), ("target", theme.cdf)],
+            Query::Rank(_) => vec![("cumulative weight", theme.element)],
+        }
+    }
+
+    fn caption(&self) -> String {
+        match self.query {
+            Query::Quantile(0.5) => "What is the median?".to_string(),
+            Query::Quantile(q) => format!("What is the {} quantile?", q),
+            Query::Rank(x) => format!("How many items are at most {}?", x),
+        }
+    }
+
+    // The caption, the progress so far and, on the last frame, the answer.
+    fn text(&self, frame: usize) -> Vec<String> {
+        let n = self.est.estimated_element_count();
+        let cumulative_weight: usize = self.est.elements[..frame].iter().map(|e| e.weight).sum();
+        let progress = match self.query {
+            Query::Quantile(q) => format!(
+                "cumulative weight {} of {}, looking for {} x {} = {}",
+                cumulative_weight,
+                n,
+                q,
+                n,
+                target_weight(&self.est, self.query)
+            ),
+            Query::Rank(_) => format!("cumulative weight {} of {}", cumulative_weight, n),
+        };
+        let mut text = vec![self.caption(), progress];
+        if frame == self.steps {
+            let bound = self.est.rank_error_bound;
+            text.push(match self.query {
+                Query::Quantile(q) => match self.est.quantile(q) {
+                    Some(answer) => format!(
+                        "About {}, whose rank is within {} ({:.1}%) of {}",
+                        answer,
+                        bound,
+                        bound as f64 / n.max(1) as f64 * 100.0,
+                        target_weight(&self.est, self.query)
+                    ),
+                    None => "The sketch is empty".to_string(),
+                },
+                Query::Rank(x) => format!(
+                    "About {}, within {} ({:.1}%) of the true rank",
+                    self.est.rank(&x),
+                    bound,
+                    bound as f64 / n.max(1) as f64 * 100.0
+                ),
+            });
+        }
+        text
+    }
+
+    pub fn render(
+        &self,
+        frame: usize,
+        theme: &Theme,
+        scale: &ValueScale,
+        renderer: &mut dyn Renderer,
+        delay: Duration,
+    ) -> Duration {
+        let layout = &theme.layout;
+        let space = layout.space_between_elements;
+        let column_width = (layout.element_width + space) as f32;
+        renderer.clear(theme.background);
+        let x = space as f32;
+        let text = self.text(frame);
+        draw_header(renderer, theme, [&text[0], &text[1]], &self.legend(theme));
+
+        let elements_y = (header_height(layout) + space) as f32;
+        let tallest = self
+            .est
+            .elements
+            .iter()
+            .map(|e| e.weight)
+            .max()
+            .unwrap_or(0) as u32;
+        let chart_y = elements_y + (max(tallest, layout.max_element_height) + space) as f32;
+        let chart_height = Self::chart_height(layout) as f32;
+        let n = self.est.estimated_element_count().max(1) as f32;
+        let mut cumulative_weight = 0;
+        for (i, element) in self.est.elements.iter().enumerate() {
+            let left = x + i as f32 * column_width;
+            renderer.fill_rect(
+                Rect::new(
+                    left,
+                    elements_y,
+                    layout.element_width as f32,
+                    element.weight as f32,
+                ),
+                color(theme, scale, element.element),
+            );
+            if i < frame {
+                cumulative_weight += element.weight;
+                let height = cumulative_weight as f32 / n * chart_height;
+                renderer.fill_rect(
+                    Rect::new(left, chart_y, layout.element_width as f32, height),
+                    theme.element,
+                );
+            }
+        }
+        if let Query::Quantile(_) = self.query {
+            let target = target_weight(&self.est, self.query) as f32 / n * chart_height;
+            renderer.fill_rect(
+                Rect::new(
+                    x,
+                    chart_y + target,
+                    self.est.elements.len() as f32 * column_width,
+                    1.0,
+                ),
+                theme.cdf,
+            );
+        }
+        let answer_y = chart_y + chart_height + space as f32;
+        if frame == self.steps {
+            // Mark the boundary between the elements that were counted and the rest.
+            let boundary = x + self.steps as f32 * column_width - space as f32 / 2.0 - 0.5;
+            renderer.fill_rect(
+                Rect::new(boundary, elements_y, 1.0, answer_y - elements_y),
+                theme.text,
+            );
+            renderer.text(x, answer_y, &text[2], theme.text);
+            delay * 8
+        } else {
+            delay
+        }
+    }
+}
+
+// The cumulative weight at which a quantile query is answered.
+fn target_weight(est: &EstimatedQuantiles<Element>, query: Query) -> usize {
+    match query {
+        Query::Quantile(q) => (q * est.estimated_element_count() as f64).ceil() as usize,
+        Query::Rank(_) => 0,
+    }
+}
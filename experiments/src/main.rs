@@ -0,0 +1,44 @@
+mod options;
+mod run;
+mod stream;
+mod table;
+
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+};
+
+use options::Options;
+use run::{run, Config};
+
+fn main() -> io::Result<()> {
+    let options = Options::from_env();
+    let mut rows = Vec::new();
+    for &distribution in &options.distributions {
+        for &length in &options.lengths {
+            for &k in &options.ks {
+                for &coefficient in &options.coefficients {
+                    for &mode in &options.modes {
+                        let config = Config {
+                            k,
+                            coefficient,
+                            mode,
+                            length,
+                            distribution,
+                        };
+                        eprintln!("{:?}", config);
+                        let runs: Vec<_> =
+                            (0..options.seeds).map(|seed| run(&config, seed)).collect();
+                        rows.push(table::row(&config, &runs));
+                    }
+                }
+            }
+        }
+    }
+    let mut w: Box<dyn Write> = match &options.output {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(io::stdout()),
+    };
+    table::write(&mut w, options.format, &rows)?;
+    w.flush()
+}
@@ -0,0 +1,114 @@
+use std::{path::PathBuf, process, str::FromStr};
+
+use compactorsanim::compactors::CAPACITY_COEFFICIENT;
+
+use crate::{run::Mode, stream::StreamKind};
+
+const USAGE: &str = "\
+Runs sketches over many random streams and writes a table of how accurate they were, how much
+they kept and how fast they were. Lists are comma separated, and every combination is run.
+
+Options:
+    --k <list>              Sizes of the largest level, such as 50,100,200
+    --coefficient <list>    How much smaller each level is than the one above it
+    --mode <list>           lazy, eager or lazy,eager
+    --length <list>         Items per stream, such as 10000,1000000
+    --distribution <list>   uniform, normal, exponential, lognormal or sorted
+    --seeds <n>             Streams per combination
+    --format <csv|markdown> How to write the table
+    --output <path>         File to write the table to, stdout when unset";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Csv,
+    Markdown,
+}
+
+#[derive(Clone, Debug)]
+pub struct Options {
+    pub ks: Vec<usize>,
+    pub coefficients: Vec<f64>,
+    pub modes: Vec<Mode>,
+    pub lengths: Vec<usize>,
+    pub distributions: Vec<StreamKind>,
+    pub seeds: u64,
+    pub format: Format,
+    pub output: Option<PathBuf>,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            ks: vec![50, 100, 200],
+            coefficients: vec![CAPACITY_COEFFICIENT],
+            modes: vec![Mode::Lazy, Mode::Eager],
+            lengths: vec![10_000, 100_000],
+            distributions: vec![StreamKind::Uniform, StreamKind::Normal],
+            seeds: 10,
+            format: Format::Markdown,
+            output: None,
+        }
+    }
+}
+
+impl Options {
+    pub fn from_env() -> Self {
+        match Self::parse(std::env::args().skip(1)) {
+            Ok(options) => options,
+            Err(message) => {
+                eprintln!("{}\n\n{}", message, USAGE);
+                process::exit(2);
+            }
+        }
+    }
+
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self, String> {
+        let mut options = Self::default();
+        let mut args = args.into_iter();
+        while let Some(flag) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| format!("{} requires a value", flag))
+            };
+            match flag.as_str() {
+                "--k" => options.ks = parse_list(&value()?, parse_number)?,
+                "--coefficient" => {
+                    options.coefficients = parse_list(&value()?, |c| {
+                        let c: f64 = parse_number(c)?;
+                        if c > 0.0 && c <= 1.0 {
+                            Ok(c)
+                        } else {
+                            Err(format!("The coefficient {} is not in (0, 1]", c))
+                        }
+                    })?
+                }
+                "--mode" => options.modes = parse_list(&value()?, Mode::parse)?,
+                "--length" => options.lengths = parse_list(&value()?, parse_number)?,
+                "--distribution" => {
+                    options.distributions = parse_list(&value()?, StreamKind::parse)?
+                }
+                "--seeds" => options.seeds = parse_number(&value()?)?,
+                "--format" => {
+                    options.format = match value()?.as_str() {
+                        "csv" => Format::Csv,
+                        "markdown" => Format::Markdown,
+                        format => return Err(format!("Unknown format {}", format)),
+                    }
+                }
+                "--output" => options.output = Some(PathBuf::from(value()?)),
+                _ => return Err(format!("Unknown option {}", flag)),
+            }
+        }
+        Ok(options)
+    }
+}
+
+fn parse_list<T>(list: &str, parse: impl Fn(&str) -> Result<T, String>) -> Result<Vec<T>, String> {
+    list.split(',').map(|item| parse(item.trim())).collect()
+}
+
+fn parse_number<N: FromStr>(value: &str) -> Result<N, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{} is not a valid number", value))
+}
@@ -0,0 +1,84 @@
+use std::time::Instant;
+
+use compactorsanim::{compactors::Compactors, frame::NoFrames};
+use distanim::{
+    error::{RankErrors, DECILES},
+    estimated_quantiles::EstimatedQuantiles,
+};
+
+use crate::stream::{StreamKind, Value};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Mode {
+    Lazy,
+    Eager,
+}
+
+impl Mode {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name {
+            "lazy" => Ok(Mode::Lazy),
+            "eager" => Ok(Mode::Eager),
+            name => Err(format!("Unknown mode {}", name)),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Mode::Lazy => "lazy",
+            Mode::Eager => "eager",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Config {
+    pub k: usize,
+    pub coefficient: f64,
+    pub mode: Mode,
+    pub length: usize,
+    pub distribution: StreamKind,
+}
+
+// What one sketch made of one stream.
+#[derive(Clone, Copy, Debug)]
+pub struct Run {
+    // The largest rank error over every item of the stream, and over the deciles only.
+    pub max_rank_error: usize,
+    pub max_decile_error: usize,
+    pub rank_error_bound: usize,
+    pub retained: usize,
+    pub total_capacity: usize,
+    pub levels: usize,
+    // Time spent on updates alone, without generating or checking the stream.
+    pub seconds: f64,
+}
+
+pub fn run(config: &Config, seed: u64) -> Run {
+    let stream = config.distribution.generate(config.length, seed);
+    match config.mode {
+        Mode::Lazy => run_with::<true>(config, stream),
+        Mode::Eager => run_with::<false>(config, stream),
+    }
+}
+
+fn run_with<const LAZY: bool>(config: &Config, mut stream: Vec<Value>) -> Run {
+    let start = Instant::now();
+    let mut compactors: Compactors<Value, _, LAZY> =
+        Compactors::with_capacity_coefficient(config.k, config.coefficient, NoFrames);
+    compactors.extend(stream.iter().copied());
+    let seconds = start.elapsed().as_secs_f64();
+    let stats = compactors.stats();
+    let est = EstimatedQuantiles::from(compactors);
+    stream.sort();
+    let errors = RankErrors::measure(&est, &stream, &DECILES);
+    Run {
+        max_rank_error: errors.max_rank_error,
+        max_decile_error: errors.quantile_errors.iter().copied().max().unwrap_or(0),
+        rank_error_bound: est.rank_error_bound,
+        retained: stats.retained,
+        total_capacity: stats.total_capacity,
+        levels: stats.levels,
+        seconds,
+    }
+}
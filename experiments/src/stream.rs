@@ -0,0 +1,70 @@
+use std::cmp::Ordering;
+
+use rand::{prelude::StdRng, SeedableRng};
+use rand_distr::{Distribution, Exp, LogNormal, Normal, Uniform};
+
+// A sampled value. Samples are never NaN, so they can be ordered like any other item.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Value(pub f64);
+
+impl Eq for Value {}
+
+impl PartialOrd for Value {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Value {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StreamKind {
+    Uniform,
+    Normal,
+    Exponential,
+    LogNormal,
+    // Increasing values, the order that sketches built on sampling find hardest.
+    Sorted,
+}
+
+impl StreamKind {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        Ok(match name {
+            "uniform" => StreamKind::Uniform,
+            "normal" => StreamKind::Normal,
+            "exponential" => StreamKind::Exponential,
+            "lognormal" => StreamKind::LogNormal,
+            "sorted" => StreamKind::Sorted,
+            name => return Err(format!("Unknown distribution {}", name)),
+        })
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            StreamKind::Uniform => "uniform",
+            StreamKind::Normal => "normal",
+            StreamKind::Exponential => "exponential",
+            StreamKind::LogNormal => "lognormal",
+            StreamKind::Sorted => "sorted",
+        }
+    }
+
+    pub fn generate(self, length: usize, seed: u64) -> Vec<Value> {
+        let r = &mut StdRng::seed_from_u64(seed);
+        match self {
+            StreamKind::Uniform => sample(Uniform::new(0.0, 1.0), length, r),
+            StreamKind::Normal => sample(Normal::new(0.0, 1.0).unwrap(), length, r),
+            StreamKind::Exponential => sample(Exp::new(1.0).unwrap(), length, r),
+            StreamKind::LogNormal => sample(LogNormal::new(0.0, 1.0).unwrap(), length, r),
+            StreamKind::Sorted => (0..length).map(|i| Value(i as f64)).collect(),
+        }
+    }
+}
+
+fn sample<D: Distribution<f64>>(d: D, length: usize, r: &mut StdRng) -> Vec<Value> {
+    d.sample_iter(r).take(length).map(Value).collect()
+}
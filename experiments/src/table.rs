@@ -0,0 +1,90 @@
+use std::io::{self, Write};
+
+use crate::{
+    options::Format,
+    run::{Config, Run},
+};
+
+const HEADER: [&str; 17] = [
+    "distribution",
+    "length",
+    "k",
+    "coefficient",
+    "mode",
+    "seeds",
+    "error p50 %",
+    "error p90 %",
+    "error p99 %",
+    "error max %",
+    "decile error max %",
+    "bound max %",
+    "retained mean",
+    "retained max",
+    "capacity max",
+    "levels max",
+    "items/s median",
+];
+
+// One row of the table, summarizing every seed of a combination. Errors are percentages of the
+// stream length, so that different lengths can be compared.
+pub fn row(config: &Config, runs: &[Run]) -> Vec<String> {
+    let percent = |ranks: usize| ranks as f64 / config.length.max(1) as f64 * 100.0;
+    let mut errors: Vec<f64> = runs.iter().map(|run| percent(run.max_rank_error)).collect();
+    errors.sort_by(f64::total_cmp);
+    let mut throughputs: Vec<f64> = runs
+        .iter()
+        .map(|run| config.length as f64 / run.seconds)
+        .collect();
+    throughputs.sort_by(f64::total_cmp);
+    let max = |f: fn(&Run) -> usize| runs.iter().map(f).max().unwrap_or(0);
+    vec![
+        config.distribution.name().to_string(),
+        config.length.to_string(),
+        config.k.to_string(),
+        config.coefficient.to_string(),
+        config.mode.name().to_string(),
+        runs.len().to_string(),
+        format!("{:.3}", percentile(&errors, 0.5)),
+        format!("{:.3}", percentile(&errors, 0.9)),
+        format!("{:.3}", percentile(&errors, 0.99)),
+        format!("{:.3}", percentile(&errors, 1.0)),
+        format!("{:.3}", percent(max(|run| run.max_decile_error))),
+        format!("{:.3}", percent(max(|run| run.rank_error_bound))),
+        format!(
+            "{:.1}",
+            runs.iter().map(|run| run.retained).sum::<usize>() as f64 / runs.len().max(1) as f64
+        ),
+        max(|run| run.retained).to_string(),
+        max(|run| run.total_capacity).to_string(),
+        max(|run| run.levels).to_string(),
+        format!("{:.0}", percentile(&throughputs, 0.5)),
+    ]
+}
+
+// The nearest-rank percentile of sorted values.
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = (p * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+pub fn write<W: Write>(w: &mut W, format: Format, rows: &[Vec<String>]) -> io::Result<()> {
+    let header: Vec<String> = HEADER.iter().map(|h| h.to_string()).collect();
+    match format {
+        Format::Csv => {
+            for row in std::iter::once(&header).chain(rows) {
+                writeln!(w, "{}", row.join(","))?;
+            }
+        }
+        Format::Markdown => {
+            writeln!(w, "| {} |", header.join(" | "))?;
+            writeln!(w, "|{}", "---|".repeat(header.len()))?;
+            for row in rows {
+                writeln!(w, "| {} |", row.join(" | "))?;
+            }
+        }
+    }
+    Ok(())
+}
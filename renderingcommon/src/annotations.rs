@@ -0,0 +1,65 @@
+use ggez::graphics::{Color, Rect};
+
+use crate::{
+    font,
+    renderer::Renderer,
+    theme::{Layout, Theme},
+};
+const LEGEND_SPACING: u32 = 2 * font::ADVANCE;
+
+// Two lines of text, such as a caption and the number of items seen, followed by the legend.
+pub fn header_height(layout: &Layout) -> u32 {
+    3 * font::LINE_HEIGHT + layout.space_between_elements
+}
+
+pub fn header_width(theme: &Theme, lines: [&str; 2], legend: &[(&str, Color)]) -> u32 {
+    theme.layout.space_between_elements
+        + lines
+            .iter()
+            .map(|line| font::text_width(line))
+            .max()
+            .unwrap_or(0)
+            .max(legend_width(legend))
+}
+
+// Draws the header in the top left corner of the frame.
+pub fn draw_header(
+    renderer: &mut dyn Renderer,
+    theme: &Theme,
+    lines: [&str; 2],
+    legend: &[(&str, Color)],
+) {
+    let space = theme.layout.space_between_elements;
+    let x = space as f32;
+    let line = |i: u32| (space + i * font::LINE_HEIGHT) as f32;
+    for (i, text) in lines.iter().enumerate() {
+        renderer.text(x, line(i as u32), text, theme.text);
+    }
+    draw_legend(renderer, theme, x, line(2), legend);
+}
+
+// Each entry is a colour swatch the height of a line of text followed by its label.
+pub fn legend_width(entries: &[(&str, Color)]) -> u32 {
+    entries
+        .iter()
+        .map(|(label, _)| font::GLYPH_HEIGHT + font::ADVANCE + font::text_width(label))
+        .sum::<u32>()
+        + LEGEND_SPACING * entries.len().saturating_sub(1) as u32
+}
+
+pub fn draw_legend(
+    renderer: &mut dyn Renderer,
+    theme: &Theme,
+    x: f32,
+    y: f32,
+    entries: &[(&str, Color)],
+) {
+    let mut x = x;
+    for (label, color) in entries {
+        let swatch = font::GLYPH_HEIGHT as f32;
+        renderer.fill_rect(Rect::new(x, y, swatch, swatch), *color);
+        x += swatch + font::ADVANCE as f32;
+        renderer.text(x, y, label, theme.text);
+        x += (font::text_width(label) + LEGEND_SPACING) as f32;
+    }
+}
@@ -0,0 +1,22 @@
+use std::f32::consts::PI;
+
+use rand_distr::Normal;
+
+pub fn normal_pdf(distribution: &Normal<f32>, x: f32) -> f32 {
+    let z = (x - distribution.mean()) / distribution.std_dev();
+    (-z * z / 2.0).exp() / (distribution.std_dev() * (2.0 * PI).sqrt())
+}
+
+pub fn normal_cdf(distribution: &Normal<f32>, x: f32) -> f32 {
+    let z = (x - distribution.mean()) / distribution.std_dev();
+    (1.0 + erf(z / 2f32.sqrt())) / 2.0
+}
+
+// Abramowitz and Stegun 7.1.26, accurate to about 1.5e-7.
+fn erf(x: f32) -> f32 {
+    let t = 1.0 / (1.0 + 0.327_591_1 * x.abs());
+    let polynomial = t
+        * (0.254_829_6
+            + t * (-0.284_496_74 + t * (1.421_413_7 + t * (-1.453_152 + t * 1.061_405_4))));
+    (1.0 - polynomial * (-x * x).exp()).copysign(x)
+}
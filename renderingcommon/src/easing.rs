@@ -0,0 +1,43 @@
+use ggez::graphics::{Color, Rect};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+impl Easing {
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => t * (2.0 - t),
+            Easing::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+pub fn lerp(from: f32, to: f32, t: f32) -> f32 {
+    from + (to - from) * t
+}
+
+pub fn lerp_rect(from: Rect, to: Rect, t: f32) -> Rect {
+    Rect::new(
+        lerp(from.x, to.x, t),
+        lerp(from.y, to.y, t),
+        lerp(from.w, to.w, t),
+        lerp(from.h, to.h, t),
+    )
+}
+
+pub fn lerp_color(from: Color, to: Color, t: f32) -> Color {
+    Color::new(
+        lerp(from.r, to.r, t),
+        lerp(from.g, to.g, t),
+        lerp(from.b, to.b, t),
+        lerp(from.a, to.a, t),
+    )
+}
@@ -0,0 +1,265 @@
+use std::{
+    path::{Path, PathBuf},
+    process,
+    str::FromStr,
+    time::Duration,
+};
+
+use crate::{
+    easing::Easing,
+    output::{OutputFormat, OutputOptions},
+    renderer::Backend,
+    scale::{Scale, ScaleRange},
+    theme::Theme,
+    viewport::ViewportOptions,
+    Element,
+};
+
+const USAGE: &str = "\
+Options:
+    --format <png|gif|apng|y4m|svg|animated-svg>
+                          Write a PNG per frame, a single animated GIF or PNG, a
+                          raw Y4M video stream, an SVG per frame or a single SVG
+                          animation. SVG is only supported by compactorsrendering
+    --output <path>       Directory for PNG or SVG frames, or the animation file.
+                          Y4M streams go to stdout when this is - or unset
+    --delay <ms>          Delay between ordinary frames in milliseconds
+    --loops <n>           Number of times the animation plays, 0 loops forever
+    --fps <n>             Frame rate of Y4M streams
+    --renderer <cpu|ggez> Draw frames in software, or with ggez which needs a GPU
+    --interactive         Open a window to step through the frames instead of writing
+                          them. Arrows step, Home/End jump to either end, a number
+                          then Enter seeks, Space plays and Up/Down change speed
+    --tween-frames <n>    In-between frames that animate elements from one step to
+                          the next, 0 cuts straight between steps. Only supported by
+                          compactorsrendering
+    --easing <linear|ease-in|ease-out|ease-in-out>
+                          How in-between frames are spaced over a transition. Only
+                          supported by compactorsrendering
+    --theme <dark|light|colorblind|path>
+                          Colours and sizes to draw with, either a preset or a TOML
+                          or JSON file; see renderingcommon/src/theme.rs
+    --scale <linear|log|rank>
+                          How values map to heights and colours
+    --scale-range <stream|distribution>
+                          Scale between the smallest and largest value in the
+                          stream, or over the practical range of the distribution
+                          the stream is sampled from
+    --zoom <factor>       Scale frames up or down, for example 2 or 1.5
+    --resolution <WxH>    Fit frames into this resolution instead of zooming
+    --integer-zoom        Only scale by whole numbers, for crisp pixels
+    --padding <px>        Empty border around the frames
+    --quantile <q>        Show how the sketch answers a quantile query, such as 0.5 for
+                          the median. Only supported by distrendering
+    --rank <x>            Show how the sketch estimates the rank of a value. Only
+                          supported by distrendering
+    --errors              Show how far the estimates are from the truth as the stream
+                          grows, instead of a query. Only supported by distrendering
+    --merge               Build two sketches from different seeds and show how one is
+                          merged into the other. Only supported by compactorsrendering";
+
+// The renderer reading the options. Each one only accepts the flags it acts on.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Program {
+    Compactors,
+    Distribution,
+}
+
+impl Program {
+    fn name(self) -> &'static str {
+        match self {
+            Program::Compactors => "compactorsrendering",
+            Program::Distribution => "distrendering",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Query {
+    Quantile(f64),
+    Rank(Element),
+}
+
+#[derive(Clone, Debug)]
+pub struct Options {
+    pub output: OutputOptions,
+    pub backend: Backend,
+    pub interactive: bool,
+    pub tween_frames: u32,
+    pub easing: Easing,
+    pub theme: Theme,
+    pub scale: Scale,
+    pub scale_range: ScaleRange,
+    pub viewport: ViewportOptions,
+    pub query: Option<Query>,
+    pub errors: bool,
+    pub merge: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            output: Default::default(),
+            backend: Backend::Cpu,
+            interactive: false,
+            tween_frames: 0,
+            easing: Easing::EaseInOut,
+            theme: Theme::dark(),
+            scale: Scale::Linear,
+            scale_range: ScaleRange::Stream,
+            viewport: Default::default(),
+            query: None,
+            errors: false,
+            merge: false,
+        }
+    }
+}
+
+impl Options {
+    pub fn from_env(program: Program) -> Self {
+        match Self::parse(program, std::env::args().skip(1)) {
+            Ok(options) => options,
+            Err(message) => {
+                eprintln!("{}\n\n{}", message, USAGE);
+                process::exit(2);
+            }
+        }
+    }
+
+    pub fn parse<I: IntoIterator<Item = String>>(
+        program: Program,
+        args: I,
+    ) -> Result<Self, String> {
+        let mut options = Self::default();
+        let mut args = args.into_iter();
+        while let Some(flag) = args.next() {
+            let only = |supported_by: Program| {
+                if program == supported_by {
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "{} is only supported by {}",
+                        flag,
+                        supported_by.name()
+                    ))
+                }
+            };
+            match flag.as_str() {
+                "--tween-frames" | "--easing" | "--merge" => only(Program::Compactors)?,
+                "--quantile" | "--rank" | "--errors" => only(Program::Distribution)?,
+                _ => {}
+            }
+            if matches!(flag.as_str(), "--quantile" | "--rank") && options.query.is_some() {
+                return Err("Only one of --quantile and --rank can be given".to_string());
+            }
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| format!("{} requires a value", flag))
+            };
+            match flag.as_str() {
+                "--format" => {
+                    options.output.format = match value()?.as_str() {
+                        "png" => OutputFormat::PngSequence,
+                        "gif" => OutputFormat::Gif,
+                        "apng" => OutputFormat::Apng,
+                        "y4m" => OutputFormat::Y4m,
+                        "svg" => OutputFormat::Svg,
+                        "animated-svg" => OutputFormat::AnimatedSvg,
+                        format => return Err(format!("Unknown format {}", format)),
+                    }
+                }
+                "--output" => options.output.path = Some(PathBuf::from(value()?)),
+                "--delay" => {
+                    options.output.frame_delay = Duration::from_millis(parse_number(&value()?)?)
+                }
+                "--loops" => options.output.loop_count = parse_number(&value()?)?,
+                "--fps" => options.output.frame_rate = parse_number(&value()?)?,
+                "--renderer" => {
+                    options.backend = match value()?.as_str() {
+                        "cpu" => Backend::Cpu,
+                        "ggez" => Backend::Ggez,
+                        backend => return Err(format!("Unknown renderer {}", backend)),
+                    }
+                }
+                "--interactive" => options.interactive = true,
+                "--tween-frames" => options.tween_frames = parse_number(&value()?)?,
+                "--easing" => {
+                    options.easing = match value()?.as_str() {
+                        "linear" => Easing::Linear,
+                        "ease-in" => Easing::EaseIn,
+                        "ease-out" => Easing::EaseOut,
+                        "ease-in-out" => Easing::EaseInOut,
+                        easing => return Err(format!("Unknown easing {}", easing)),
+                    }
+                }
+                "--theme" => {
+                    let theme = value()?;
+                    options.theme = match Theme::preset(&theme) {
+                        Some(preset) => preset,
+                        None => Theme::from_file(Path::new(&theme))
+                            .map_err(|e| format!("Could not load theme {}: {}", theme, e))?,
+                    }
+                }
+                "--scale" => {
+                    options.scale = match value()?.as_str() {
+                        "linear" => Scale::Linear,
+                        "log" => Scale::Log,
+                        "rank" => Scale::Rank,
+                        scale => return Err(format!("Unknown scale {}", scale)),
+                    }
+                }
+                "--scale-range" => {
+                    options.scale_range = match value()?.as_str() {
+                        "stream" => ScaleRange::Stream,
+                        "distribution" => ScaleRange::Distribution,
+                        range => return Err(format!("Unknown scale range {}", range)),
+                    }
+                }
+                "--zoom" => {
+                    let zoom: f32 = parse_number(&value()?)?;
+                    if !(zoom.is_finite() && zoom > 0.0) {
+                        return Err(format!("The zoom {} is not a positive number", zoom));
+                    }
+                    options.viewport.zoom = zoom
+                }
+                "--resolution" => {
+                    let resolution = value()?;
+                    options.viewport.resolution = match resolution.split_once('x') {
+                        Some((width, height)) => {
+                            Some((parse_number(width)?, parse_number(height)?))
+                        }
+                        None => {
+                            return Err(format!(
+                                "{} is not a resolution like 1920x1080",
+                                resolution
+                            ))
+                        }
+                    }
+                }
+                "--integer-zoom" => options.viewport.integer_zoom = true,
+                "--padding" => options.viewport.padding = parse_number(&value()?)?,
+                "--quantile" => {
+                    let q = parse_number(&value()?)?;
+                    if !(0.0..=1.0).contains(&q) {
+                        return Err(format!("The quantile {} is not between 0 and 1", q));
+                    }
+                    options.query = Some(Query::Quantile(q))
+                }
+                "--rank" => options.query = Some(Query::Rank(parse_number(&value()?)?)),
+                "--errors" => options.errors = true,
+                "--merge" => options.merge = true,
+                _ => return Err(format!("Unknown option {}", flag)),
+            }
+        }
+        if options.errors && options.query.is_some() {
+            return Err("--errors cannot be combined with --quantile or --rank".to_string());
+        }
+        Ok(options)
+    }
+}
+
+fn parse_number<N: FromStr>(value: &str) -> Result<N, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{} is not a valid number", value))
+}
@@ -0,0 +1,352 @@
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    fs::File,
+    io::{self, BufWriter, Read, Write},
+    path::PathBuf,
+    time::Duration,
+};
+
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+use gif::{Encoder, Repeat};
+use png::{BitDepth, ColorType};
+
+// How hard NeuQuant works on frames with more colours than a GIF palette holds, from 1, the
+// best, to 30, the fastest.
+const GIF_QUANTIZER_SPEED: i32 = 10;
+
+pub trait FrameWriter {
+    fn write_frame(&mut self, frame: &[u8], delay: Duration) -> io::Result<()>;
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    PngSequence,
+    Gif,
+    Apng,
+    Y4m,
+    // Vector output is written straight from frame data by renderers that support it.
+    Svg,
+    AnimatedSvg,
+}
+
+#[derive(Clone, Debug)]
+pub struct OutputOptions {
+    pub format: OutputFormat,
+    pub path: Option<PathBuf>,
+    // The delay for ordinary frames, renderers lengthen it after significant events.
+    pub frame_delay: Duration,
+    // 0 loops forever, matching the GIF and APNG conventions.
+    pub loop_count: u16,
+    pub frame_rate: u32,
+}
+
+impl Default for OutputOptions {
+    fn default() -> Self {
+        Self {
+            format: OutputFormat::PngSequence,
+            path: None,
+            frame_delay: Duration::from_millis(100),
+            loop_count: 0,
+            frame_rate: 30,
+        }
+    }
+}
+
+impl OutputOptions {
+    pub fn create(&self, width: u32, height: u32) -> io::Result<Box<dyn FrameWriter>> {
+        Ok(match self.format {
+            OutputFormat::PngSequence => Box::new(PngSequence {
+                directory: self.path.clone().unwrap_or_else(|| PathBuf::from(".")),
+                width,
+                height,
+                frame_id: 0,
+            }),
+            OutputFormat::Gif => Box::new(GifWriter::new(
+                BufWriter::new(File::create(
+                    self.path
+                        .clone()
+                        .unwrap_or_else(|| PathBuf::from("animation.gif")),
+                )?),
+                width,
+                height,
+                self.loop_count,
+            )?),
+            OutputFormat::Apng => Box::new(ApngWriter::new(
+                BufWriter::new(File::create(
+                    self.path
+                        .clone()
+                        .unwrap_or_else(|| PathBuf::from("animation.png")),
+                )?),
+                width,
+                height,
+                self.loop_count,
+            )),
+            OutputFormat::Svg | OutputFormat::AnimatedSvg => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "SVG output is not supported by this renderer",
+                ))
+            }
+            OutputFormat::Y4m => match &self.path {
+                Some(path) if path.as_os_str() != "-" => Box::new(Y4mWriter::new(
+                    BufWriter::new(File::create(path)?),
+                    width,
+                    height,
+                    self.frame_rate,
+                )?),
+                _ => Box::new(Y4mWriter::new(
+                    BufWriter::new(io::stdout()),
+                    width,
+                    height,
+                    self.frame_rate,
+                )?),
+            },
+        })
+    }
+}
+
+pub struct PngSequence {
+    directory: PathBuf,
+    width: u32,
+    height: u32,
+    frame_id: u32,
+}
+
+impl FrameWriter for PngSequence {
+    fn write_frame(&mut self, frame: &[u8], _delay: Duration) -> io::Result<()> {
+        let mut encoder = png::Encoder::new(
+            BufWriter::new(File::create(self.directory.join(format!(
+                "frame{:0w$}.png",
+                self.frame_id,
+                w = 4
+            )))?),
+            self.width,
+            self.height,
+        );
+        encoder.set_color(ColorType::Rgba);
+        encoder.set_depth(BitDepth::Eight);
+        encoder.write_header()?.write_image_data(frame)?;
+        self.frame_id += 1;
+        Ok(())
+    }
+}
+
+pub struct GifWriter<W: Write> {
+    encoder: Encoder<W>,
+    width: u16,
+    height: u16,
+}
+
+impl<W: Write> GifWriter<W> {
+    pub fn new(w: W, width: u32, height: u32, loop_count: u16) -> io::Result<Self> {
+        let width = gif_dimension(width)?;
+        let height = gif_dimension(height)?;
+        let mut encoder = Encoder::new(w, width, height, &[]).map_err(gif_error)?;
+        encoder
+            .set_repeat(if loop_count == 0 {
+                Repeat::Infinite
+            } else {
+                Repeat::Finite(loop_count)
+            })
+            .map_err(gif_error)?;
+        Ok(Self {
+            encoder,
+            width,
+            height,
+        })
+    }
+}
+
+impl<W: Write> FrameWriter for GifWriter<W> {
+    fn write_frame(&mut self, frame: &[u8], delay: Duration) -> io::Result<()> {
+        // Every frame carries its own palette, so themes and blended colours come out as drawn.
+        let mut frame = match exact_palette(frame) {
+            Some((palette, buffer)) => gif::Frame {
+                width: self.width,
+                height: self.height,
+                palette: Some(palette),
+                buffer: buffer.into(),
+                ..Default::default()
+            },
+            None => gif::Frame::from_rgba_speed(
+                self.width,
+                self.height,
+                &mut frame.to_vec(),
+                GIF_QUANTIZER_SPEED,
+            ),
+        };
+        frame.delay = gif_delay(delay);
+        self.encoder.write_frame(&frame).map_err(gif_error)
+    }
+    fn finish(&mut self) -> io::Result<()> {
+        self.encoder.get_mut().flush()
+    }
+}
+
+// APNG needs the frame count up front, so frames are buffered until finish. They are kept
+// deflated, which shrinks the flat colours the renderers draw to a small fraction of their size,
+// and only the last one is kept as it is. Identical consecutive frames are collapsed into one
+// longer frame.
+pub struct ApngWriter<W: Write> {
+    w: Option<W>,
+    width: u32,
+    height: u32,
+    loop_count: u16,
+    last_frame: Vec<u8>,
+    frames: Vec<(Vec<u8>, Duration)>,
+}
+
+impl<W: Write> ApngWriter<W> {
+    pub fn new(w: W, width: u32, height: u32, loop_count: u16) -> Self {
+        Self {
+            w: Some(w),
+            width,
+            height,
+            loop_count,
+            last_frame: Vec::new(),
+            frames: Vec::new(),
+        }
+    }
+}
+
+impl<W: Write> FrameWriter for ApngWriter<W> {
+    fn write_frame(&mut self, frame: &[u8], delay: Duration) -> io::Result<()> {
+        match self.frames.last_mut() {
+            Some((_, previous_delay)) if self.last_frame == frame => *previous_delay += delay,
+            _ => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), Compression::fast());
+                encoder.write_all(frame)?;
+                self.frames.push((encoder.finish()?, delay));
+                self.last_frame.clear();
+                self.last_frame.extend_from_slice(frame);
+            }
+        }
+        Ok(())
+    }
+    fn finish(&mut self) -> io::Result<()> {
+        let w = match self.w.take() {
+            Some(w) => w,
+            None => return Ok(()),
+        };
+        if self.frames.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "An animated PNG needs at least one frame",
+            ));
+        }
+        let mut encoder = png::Encoder::new(w, self.width, self.height);
+        encoder.set_color(ColorType::Rgba);
+        encoder.set_depth(BitDepth::Eight);
+        encoder.set_animated(self.frames.len() as u32, self.loop_count as u32)?;
+        let mut writer = encoder.write_header()?;
+        let mut frame = Vec::with_capacity(self.last_frame.len());
+        for (compressed, delay) in self.frames.drain(..) {
+            frame.clear();
+            ZlibDecoder::new(compressed.as_slice()).read_to_end(&mut frame)?;
+            writer.set_frame_delay(delay.as_millis().min(u16::MAX as u128) as u16, 1000)?;
+            writer.write_image_data(&frame)?;
+        }
+        writer.finish()?;
+        Ok(())
+    }
+}
+
+// Writes an uncompressed YUV4MPEG2 stream, which ffmpeg and most other encoders accept on
+// stdin. Frame delays are approximated by repeating frames at the fixed frame rate.
+pub struct Y4mWriter<W: Write> {
+    w: W,
+    frame_rate: u32,
+    planes: Vec<u8>,
+}
+
+impl<W: Write> Y4mWriter<W> {
+    pub fn new(mut w: W, width: u32, height: u32, frame_rate: u32) -> io::Result<Self> {
+        if frame_rate == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "The frame rate must be at least 1",
+            ));
+        }
+        writeln!(
+            w,
+            "YUV4MPEG2 W{} H{} F{}:1 Ip A1:1 C444",
+            width, height, frame_rate
+        )?;
+        Ok(Self {
+            w,
+            frame_rate,
+            planes: vec![0; width as usize * height as usize * 3],
+        })
+    }
+}
+
+impl<W: Write> FrameWriter for Y4mWriter<W> {
+    fn write_frame(&mut self, frame: &[u8], delay: Duration) -> io::Result<()> {
+        let pixels = self.planes.len() / 3;
+        let (y, uv) = self.planes.split_at_mut(pixels);
+        let (u, v) = uv.split_at_mut(pixels);
+        for (i, pixel) in frame.chunks_exact(4).take(pixels).enumerate() {
+            let (r, g, b) = (pixel[0] as i32, pixel[1] as i32, pixel[2] as i32);
+            // BT.601 studio swing, the default that encoders assume for Y4M input.
+            y[i] = (((66 * r + 129 * g + 25 * b + 128) >> 8) + 16) as u8;
+            u[i] = (((-38 * r - 74 * g + 112 * b + 128) >> 8) + 128) as u8;
+            v[i] = (((112 * r - 94 * g - 18 * b + 128) >> 8) + 128) as u8;
+        }
+        let repeats = ((delay.as_secs_f64() * self.frame_rate as f64).round() as u64).max(1);
+        for _ in 0..repeats {
+            self.w.write_all(b"FRAME\n")?;
+            self.w.write_all(&self.planes)?;
+        }
+        Ok(())
+    }
+    fn finish(&mut self) -> io::Result<()> {
+        self.w.flush()
+    }
+}
+
+// A palette of exactly the colours in the frame, and the frame as indices into it, as long as
+// they fit in a GIF palette. The renderers only draw a handful of colours, so this is the
+// usual case.
+fn exact_palette(frame: &[u8]) -> Option<(Vec<u8>, Vec<u8>)> {
+    let mut indices: HashMap<&[u8], u8> = HashMap::new();
+    let mut palette = Vec::new();
+    let buffer = frame
+        .chunks_exact(4)
+        .map(|pixel| {
+            let rgb = &pixel[..3];
+            if let Some(&index) = indices.get(rgb) {
+                return Some(index);
+            }
+            let index = u8::try_from(indices.len()).ok()?;
+            indices.insert(rgb, index);
+            palette.extend_from_slice(rgb);
+            Some(index)
+        })
+        .collect::<Option<Vec<u8>>>()?;
+    Some((palette, buffer))
+}
+
+fn gif_delay(frame_delay: Duration) -> u16 {
+    // GIF delays are in hundredths of a second.
+    ((frame_delay.as_millis() + 5) / 10).min(u16::MAX as u128) as u16
+}
+
+fn gif_dimension(size: u32) -> io::Result<u16> {
+    u16::try_from(size).map_err(|_| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is too large for a GIF dimension", size),
+        )
+    })
+}
+
+fn gif_error(error: gif::EncodingError) -> io::Error {
+    match error {
+        gif::EncodingError::Io(error) => error,
+        error => io::Error::other(error.to_string()),
+    }
+}
@@ -0,0 +1,204 @@
+use std::time::Duration;
+
+use ggez::{
+    conf::NumSamples,
+    graphics::{
+        self, get_window_color_format, Canvas, Color, DrawMode, DrawParam, MeshBuilder, Rect,
+    },
+    Context, GameError, GameResult,
+};
+
+use crate::{font, options::Options};
+
+pub trait Renderer {
+    fn clear(&mut self, color: Color);
+    fn fill_rect(&mut self, rect: Rect, color: Color);
+    fn read_pixels(&mut self) -> GameResult<Vec<u8>>;
+
+    fn text(&mut self, x: f32, y: f32, text: &str, color: Color) {
+        self.scaled_text(x, y, 1.0, text, color);
+    }
+
+    // Draws text with its top left corner at (x, y), with every pixel of the bundled font `scale`
+    // pixels across. Renderers that can draw text themselves override this.
+    fn scaled_text(&mut self, x: f32, y: f32, scale: f32, text: &str, color: Color) {
+        for (i, c) in text.chars().enumerate() {
+            for (column, bits) in font::glyph(c).iter().enumerate() {
+                for row in 0..font::GLYPH_HEIGHT {
+                    if bits >> row & 1 == 1 {
+                        self.fill_rect(
+                            Rect::new(
+                                x + (i as u32 * font::ADVANCE + column as u32) as f32 * scale,
+                                y + row as f32 * scale,
+                                scale,
+                                scale,
+                            ),
+                            color,
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    Cpu,
+    Ggez,
+}
+
+// An RGBA8 software framebuffer, which needs neither a window nor a GPU.
+pub struct Framebuffer {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Framebuffer {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; width as usize * height as usize * 4],
+        }
+    }
+}
+
+impl Renderer for Framebuffer {
+    fn clear(&mut self, color: Color) {
+        let (r, g, b, a) = color.to_rgba();
+        for pixel in self.pixels.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&[r, g, b, a]);
+        }
+    }
+
+    fn fill_rect(&mut self, rect: Rect, color: Color) {
+        let clamp = |v: f32, max: u32| v.round().clamp(0.0, max as f32) as usize;
+        let (left, right) = (rect.x.min(rect.x + rect.w), rect.x.max(rect.x + rect.w));
+        let (top, bottom) = (rect.y.min(rect.y + rect.h), rect.y.max(rect.y + rect.h));
+        let (x0, x1) = (clamp(left, self.width), clamp(right, self.width));
+        let (y0, y1) = (clamp(top, self.height), clamp(bottom, self.height));
+        let alpha = color.a.clamp(0.0, 1.0);
+        let source = [color.r, color.g, color.b];
+        for y in y0..y1 {
+            let row = y * self.width as usize * 4;
+            for pixel in self.pixels[row + x0 * 4..row + x1 * 4].chunks_exact_mut(4) {
+                for (channel, &source) in pixel.iter_mut().zip(source.iter()) {
+                    let blended = source * alpha + (*channel as f32 / 255.0) * (1.0 - alpha);
+                    *channel = (blended.clamp(0.0, 1.0) * 255.0).round() as u8;
+                }
+                let destination_alpha = pixel[3] as f32 / 255.0;
+                pixel[3] = ((alpha + destination_alpha * (1.0 - alpha)) * 255.0).round() as u8;
+            }
+        }
+    }
+
+    fn read_pixels(&mut self) -> GameResult<Vec<u8>> {
+        Ok(self.pixels.clone())
+    }
+}
+
+// Batches rectangles into a single mesh, drawn to the canvas when one is given and to the window
+// otherwise.
+pub struct GgezRenderer<'a> {
+    ctx: &'a mut Context,
+    canvas: Option<&'a Canvas>,
+    rects: Vec<(Rect, Color)>,
+}
+
+impl<'a> GgezRenderer<'a> {
+    pub fn new(ctx: &'a mut Context, canvas: Option<&'a Canvas>) -> Self {
+        graphics::set_canvas(ctx, canvas);
+        Self {
+            ctx,
+            canvas,
+            rects: Vec::new(),
+        }
+    }
+
+    pub fn present(mut self) -> GameResult {
+        self.flush()?;
+        graphics::present(self.ctx)
+    }
+
+    fn flush(&mut self) -> GameResult {
+        if self.rects.is_empty() {
+            return Ok(());
+        }
+        let mut mesh_builder = MeshBuilder::new();
+        for (rect, color) in self.rects.drain(..) {
+            mesh_builder.rectangle(DrawMode::fill(), rect, color)?;
+        }
+        let mesh = mesh_builder.build(self.ctx)?;
+        graphics::draw(self.ctx, &mesh, DrawParam::default())
+    }
+}
+
+impl Renderer for GgezRenderer<'_> {
+    fn clear(&mut self, color: Color) {
+        self.rects.clear();
+        graphics::clear(self.ctx, color);
+    }
+
+    fn fill_rect(&mut self, rect: Rect, color: Color) {
+        self.rects.push((rect, color));
+    }
+
+    fn read_pixels(&mut self) -> GameResult<Vec<u8>> {
+        self.flush()?;
+        match self.canvas {
+            Some(canvas) => canvas.to_rgba8(self.ctx),
+            None => Err(GameError::RenderError(
+                "Pixels can only be read back from a canvas".to_string(),
+            )),
+        }
+    }
+}
+
+// Renders every frame with the configured backend and writes it to the configured output.
+// `render` draws a frame and returns how long it should be shown for.
+pub fn render_frames<F>(
+    options: &Options,
+    width: u32,
+    height: u32,
+    frame_count: usize,
+    mut render: F,
+) -> GameResult
+where
+    F: FnMut(usize, &mut dyn Renderer) -> Duration,
+{
+    let mut output = options.output.create(width, height)?;
+    match options.backend {
+        Backend::Cpu => {
+            let mut framebuffer = Framebuffer::new(width, height);
+            for frame in 0..frame_count {
+                let delay = render(frame, &mut framebuffer);
+                output.write_frame(&framebuffer.read_pixels()?, delay)?;
+            }
+        }
+        Backend::Ggez => {
+            let (mut ctx, _event_loop) =
+                ggez::ContextBuilder::new("quantiles", "jedmonds").build()?;
+            let color_format = get_window_color_format(&ctx);
+            let canvas = Canvas::new(
+                &mut ctx,
+                width as u16,
+                height as u16,
+                NumSamples::One,
+                color_format,
+            )?;
+            graphics::set_screen_coordinates(
+                &mut ctx,
+                Rect::new_i32(0, 0, width as i32, height as i32),
+            )?;
+            for frame in 0..frame_count {
+                let mut renderer = GgezRenderer::new(&mut ctx, Some(&canvas));
+                let delay = render(frame, &mut renderer);
+                output.write_frame(&renderer.read_pixels()?, delay)?;
+            }
+        }
+    }
+    output.finish()?;
+    Ok(())
+}
@@ -0,0 +1,91 @@
+use crate::{DISTRIBUTION_MIN, DISTRIBUTION_PRACTICAL_RANGE};
+
+// Elements are never drawn shorter than this, so that the smallest values stay visible.
+pub const MIN_ELEMENT_HEIGHT: u32 = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scale {
+    Linear,
+    Log,
+    // Spaces the distinct values evenly, however far apart they are.
+    Rank,
+}
+
+// Where the smallest and largest value of a scale come from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScaleRange {
+    Stream,
+    // The practical range of the distribution the renderers sample from, so that frames from
+    // different streams are drawn alike.
+    Distribution,
+}
+
+// Maps values onto [0, 1] so that they can be drawn as heights or colours.
+#[derive(Clone, Debug)]
+pub struct ValueScale {
+    scale: Scale,
+    min: f32,
+    max: f32,
+    // The distinct values in order, used by the rank scale.
+    values: Vec<f32>,
+}
+
+impl ValueScale {
+    pub fn new<I: IntoIterator<Item = f32>>(scale: Scale, range: ScaleRange, values: I) -> Self {
+        match range {
+            ScaleRange::Stream => Self::from_values(scale, values),
+            ScaleRange::Distribution => Self::from_range(
+                scale,
+                *DISTRIBUTION_MIN,
+                *DISTRIBUTION_MIN + *DISTRIBUTION_PRACTICAL_RANGE,
+            ),
+        }
+    }
+
+    // Scales between the smallest and largest of the values in the stream.
+    pub fn from_values<I: IntoIterator<Item = f32>>(scale: Scale, values: I) -> Self {
+        let mut values: Vec<f32> = values.into_iter().filter(|v| !v.is_nan()).collect();
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        values.dedup();
+        Self {
+            scale,
+            min: values.first().copied().unwrap_or(0.0),
+            max: values.last().copied().unwrap_or(0.0),
+            values,
+        }
+    }
+
+    // Scales over a known range, such as the practical range of the distribution being sampled.
+    // The rank scale needs the values themselves, so over a bare range it is linear.
+    pub fn from_range(scale: Scale, min: f32, max: f32) -> Self {
+        Self {
+            scale,
+            min,
+            max,
+            values: Vec::new(),
+        }
+    }
+
+    pub fn fraction(&self, value: f32) -> f32 {
+        let range = self.max - self.min;
+        let fraction = match self.scale {
+            Scale::Rank if !self.values.is_empty() => {
+                if self.values.len() == 1 {
+                    return 1.0;
+                }
+                let rank = self.values.partition_point(|&v| v < value);
+                rank as f32 / (self.values.len() - 1) as f32
+            }
+            _ if range <= 0.0 => return 1.0,
+            Scale::Log => (value - self.min + 1.0).max(1.0).ln() / (range + 1.0).ln(),
+            _ => (value - self.min) / range,
+        };
+        fraction.clamp(0.0, 1.0)
+    }
+
+    pub fn height(&self, value: f32, max_height: u32) -> u32 {
+        let max_height = max_height.max(MIN_ELEMENT_HEIGHT);
+        MIN_ELEMENT_HEIGHT
+            + (self.fraction(value) * (max_height - MIN_ELEMENT_HEIGHT) as f32).round() as u32
+    }
+}
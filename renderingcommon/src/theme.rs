@@ -0,0 +1,166 @@
+use std::{fs, io, path::Path};
+
+use ggez::graphics::Color;
+use serde::{Deserialize, Deserializer};
+
+use crate::easing::lerp_color;
+
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Layout {
+    pub element_width: u32,
+    pub space_between_elements: u32,
+    pub max_element_height: u32,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            element_width: 10,
+            space_between_elements: 2,
+            max_element_height: 20,
+        }
+    }
+}
+
+// Colours are written as "#rrggbb" or "#rrggbbaa". Anything left out of a theme file is taken
+// from the dark theme.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    #[serde(deserialize_with = "hex_color")]
+    pub background: Color,
+    #[serde(deserialize_with = "hex_color")]
+    pub text: Color,
+    #[serde(deserialize_with = "hex_color")]
+    pub element: Color,
+    #[serde(deserialize_with = "hex_color")]
+    pub discarded_element: Color,
+    #[serde(deserialize_with = "hex_color")]
+    pub capacity: Color,
+    #[serde(deserialize_with = "hex_color")]
+    pub used_capacity: Color,
+    // Items coloured by value are shaded from low_value to high_value.
+    #[serde(deserialize_with = "hex_color")]
+    pub low_value: Color,
+    #[serde(deserialize_with = "hex_color")]
+    pub high_value: Color,
+    // Overlays showing what an estimate should look like.
+    #[serde(deserialize_with = "hex_color")]
+    pub pdf: Color,
+    #[serde(deserialize_with = "hex_color")]
+    pub cdf: Color,
+    #[serde(deserialize_with = "hex_color")]
+    pub histogram: Color,
+    pub layout: Layout,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl Theme {
+    pub fn dark() -> Self {
+        Self {
+            background: Color::BLACK,
+            text: Color::new(0.85, 0.85, 0.85, 1.0),
+            element: Color::RED,
+            discarded_element: Color::new(0.35, 0.0, 0.0, 1.0),
+            capacity: Color::new(0.1, 0.1, 0.1, 1.0),
+            used_capacity: Color::new(0.25, 0.25, 0.25, 1.0),
+            low_value: Color::BLACK,
+            high_value: Color::WHITE,
+            pdf: Color::new(0.2, 0.8, 1.0, 1.0),
+            cdf: Color::new(1.0, 0.8, 0.2, 1.0),
+            histogram: Color::new(0.4, 1.0, 0.4, 1.0),
+            layout: Layout::default(),
+        }
+    }
+
+    pub fn light() -> Self {
+        Self {
+            background: Color::WHITE,
+            text: Color::new(0.1, 0.1, 0.1, 1.0),
+            element: Color::new(0.8, 0.1, 0.1, 1.0),
+            discarded_element: Color::new(0.95, 0.7, 0.7, 1.0),
+            capacity: Color::new(0.93, 0.93, 0.93, 1.0),
+            used_capacity: Color::new(0.78, 0.78, 0.78, 1.0),
+            low_value: Color::new(0.8, 0.8, 0.8, 1.0),
+            high_value: Color::BLACK,
+            pdf: Color::new(0.0, 0.35, 0.8, 1.0),
+            cdf: Color::new(0.8, 0.45, 0.0, 1.0),
+            histogram: Color::new(0.0, 0.55, 0.2, 1.0),
+            layout: Layout::default(),
+        }
+    }
+
+    // Built from the Okabe-Ito palette, which stays distinguishable with the common forms of
+    // colour blindness.
+    pub fn colorblind() -> Self {
+        Self {
+            element: Color::from_rgb(0xe6, 0x9f, 0x00),
+            discarded_element: Color::from_rgb(0x00, 0x72, 0xb2),
+            low_value: Color::from_rgb(0x00, 0x72, 0xb2),
+            high_value: Color::from_rgb(0xf0, 0xe4, 0x42),
+            pdf: Color::from_rgb(0x56, 0xb4, 0xe9),
+            cdf: Color::from_rgb(0xcc, 0x79, 0xa7),
+            histogram: Color::from_rgb(0x00, 0x9e, 0x73),
+            ..Self::dark()
+        }
+    }
+
+    pub fn preset(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Self::dark()),
+            "light" => Some(Self::light()),
+            "colorblind" => Some(Self::colorblind()),
+            _ => None,
+        }
+    }
+
+    // Reads a theme from a .json file, or from TOML otherwise.
+    pub fn from_file(path: &Path) -> io::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        let invalid_data = |e: String| io::Error::new(io::ErrorKind::InvalidData, e);
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            serde_json::from_str(&contents).map_err(|e| invalid_data(e.to_string()))
+        } else {
+            toml::from_str(&contents).map_err(|e| invalid_data(e.to_string()))
+        }
+    }
+
+    // Shades a value in [0, 1] between low_value and high_value.
+    pub fn value_color(&self, value: f32) -> Color {
+        lerp_color(self.low_value, self.high_value, value.clamp(0.0, 1.0))
+    }
+}
+
+fn hex_color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    let digits = value.strip_prefix('#').unwrap_or(&value);
+    let channel = |i: usize| {
+        digits
+            .get(i * 2..i * 2 + 2)
+            .and_then(|channel| u8::from_str_radix(channel, 16).ok())
+    };
+    let color = match digits.len() {
+        6 => channel(0)
+            .zip(channel(1))
+            .zip(channel(2))
+            .map(|((r, g), b)| Color::from_rgb(r, g, b)),
+        8 => channel(0)
+            .zip(channel(1))
+            .zip(channel(2))
+            .zip(channel(3))
+            .map(|(((r, g), b), a)| Color::from_rgba(r, g, b, a)),
+        _ => None,
+    };
+    color.ok_or_else(|| {
+        serde::de::Error::custom(format!("{} is not a #rrggbb or #rrggbbaa colour", value))
+    })
+}
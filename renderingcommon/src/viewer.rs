@@ -0,0 +1,169 @@
+use std::time::Duration;
+
+use ggez::{
+    conf::WindowMode,
+    event::{self, EventHandler, KeyCode, KeyMods},
+    graphics::{self, Rect},
+    timer, Context, GameError, GameResult,
+};
+
+use crate::renderer::{GgezRenderer, Renderer};
+
+const MIN_SPEED: f32 = 1.0 / 16.0;
+const MAX_SPEED: f32 = 16.0;
+
+// Steps through pre-rendered frames with the keyboard:
+//   Left/Right      previous/next frame
+//   Home/End        first/last frame
+//   <number> Enter  jump to a frame
+//   Space           toggle autoplay
+//   Up/Down         double/halve the autoplay speed
+pub struct Viewer<F>
+where
+    F: FnMut(usize, &mut dyn Renderer) -> Duration,
+{
+    frame_count: usize,
+    render: F,
+    current_frame: usize,
+    current_delay: Duration,
+    elapsed: Duration,
+    playing: bool,
+    speed: f32,
+    seek: String,
+}
+
+impl<F> Viewer<F>
+where
+    F: FnMut(usize, &mut dyn Renderer) -> Duration,
+{
+    pub fn new(frame_count: usize, render: F) -> Self {
+        Self {
+            frame_count,
+            render,
+            current_frame: 0,
+            current_delay: Duration::default(),
+            elapsed: Duration::default(),
+            playing: false,
+            speed: 1.0,
+            seek: String::new(),
+        }
+    }
+
+    fn last_frame(&self) -> usize {
+        self.frame_count.saturating_sub(1)
+    }
+
+    fn go_to(&mut self, frame: usize) {
+        self.current_frame = frame.min(self.last_frame());
+        self.elapsed = Duration::default();
+    }
+
+    fn title(&self) -> String {
+        format!(
+            "Frame {}/{}{} ({}x){}",
+            self.current_frame,
+            self.last_frame(),
+            if self.playing { ", playing" } else { "" },
+            self.speed,
+            if self.seek.is_empty() {
+                String::new()
+            } else {
+                format!(", go to {}", self.seek)
+            }
+        )
+    }
+}
+
+impl<F> EventHandler<GameError> for Viewer<F>
+where
+    F: FnMut(usize, &mut dyn Renderer) -> Duration,
+{
+    fn update(&mut self, ctx: &mut Context) -> GameResult {
+        if self.playing {
+            self.elapsed += timer::delta(ctx).mul_f32(self.speed);
+            while self.elapsed >= self.current_delay {
+                if self.current_frame == self.last_frame() {
+                    self.playing = false;
+                    break;
+                }
+                self.current_frame += 1;
+                // Without a delay, from --delay 0 or before the first draw, nothing would ever
+                // stop the loop, so show one frame per update instead.
+                if self.current_delay.is_zero() {
+                    self.elapsed = Duration::default();
+                    break;
+                }
+                self.elapsed -= self.current_delay;
+            }
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        graphics::set_window_title(ctx, &self.title());
+        let mut renderer = GgezRenderer::new(ctx, None);
+        if self.frame_count > 0 {
+            self.current_delay = (self.render)(self.current_frame, &mut renderer);
+        }
+        renderer.present()
+    }
+
+    fn key_down_event(&mut self, ctx: &mut Context, keycode: KeyCode, _: KeyMods, _: bool) {
+        match keycode {
+            KeyCode::Escape => event::quit(ctx),
+            KeyCode::Left => self.go_to(self.current_frame.saturating_sub(1)),
+            KeyCode::Right => self.go_to(self.current_frame + 1),
+            KeyCode::Home => self.go_to(0),
+            KeyCode::End => self.go_to(self.last_frame()),
+            KeyCode::Space => {
+                if !self.playing && self.current_frame == self.last_frame() {
+                    self.go_to(0);
+                }
+                self.playing = !self.playing;
+            }
+            KeyCode::Up => self.speed = (self.speed * 2.0).min(MAX_SPEED),
+            KeyCode::Down => self.speed = (self.speed / 2.0).max(MIN_SPEED),
+            KeyCode::Back => {
+                self.seek.pop();
+            }
+            KeyCode::Return | KeyCode::NumpadEnter => {
+                if let Ok(frame) = self.seek.parse() {
+                    self.go_to(frame);
+                }
+                self.seek.clear();
+            }
+            _ => {
+                if let Some(digit) = digit(keycode) {
+                    self.seek.push(digit);
+                }
+            }
+        }
+    }
+}
+
+fn digit(keycode: KeyCode) -> Option<char> {
+    Some(match keycode {
+        KeyCode::Key0 | KeyCode::Numpad0 => '0',
+        KeyCode::Key1 | KeyCode::Numpad1 => '1',
+        KeyCode::Key2 | KeyCode::Numpad2 => '2',
+        KeyCode::Key3 | KeyCode::Numpad3 => '3',
+        KeyCode::Key4 | KeyCode::Numpad4 => '4',
+        KeyCode::Key5 | KeyCode::Numpad5 => '5',
+        KeyCode::Key6 | KeyCode::Numpad6 => '6',
+        KeyCode::Key7 | KeyCode::Numpad7 => '7',
+        KeyCode::Key8 | KeyCode::Numpad8 => '8',
+        KeyCode::Key9 | KeyCode::Numpad9 => '9',
+        _ => return None,
+    })
+}
+
+pub fn run<F>(width: u32, height: u32, frame_count: usize, render: F) -> GameResult
+where
+    F: FnMut(usize, &mut dyn Renderer) -> Duration + 'static,
+{
+    let (mut ctx, event_loop) = ggez::ContextBuilder::new("quantiles", "jedmonds")
+        .window_mode(WindowMode::default().dimensions(width as f32, height as f32))
+        .build()?;
+    graphics::set_screen_coordinates(&mut ctx, Rect::new_i32(0, 0, width as i32, height as i32))?;
+    event::run(ctx, event_loop, Viewer::new(frame_count, render))
+}
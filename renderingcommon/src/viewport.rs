@@ -0,0 +1,169 @@
+use std::io;
+
+use ggez::{
+    graphics::{Color, Rect},
+    GameResult,
+};
+
+use crate::renderer::Renderer;
+
+// Canvases are addressed with u16 by ggez, so nothing larger can be rendered.
+pub const MAX_CANVAS_SIZE: u32 = u16::MAX as u32;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ViewportOptions {
+    // Fit the frames into this many pixels instead of scaling them by `zoom`.
+    pub resolution: Option<(u32, u32)>,
+    pub zoom: f32,
+    // Round the zoom down to a whole number so that every pixel is scaled by the same amount.
+    pub integer_zoom: bool,
+    pub padding: u32,
+}
+
+impl Default for ViewportOptions {
+    fn default() -> Self {
+        Self {
+            resolution: None,
+            zoom: 1.0,
+            integer_zoom: false,
+            padding: 0,
+        }
+    }
+}
+
+// Places frames laid out in unscaled pixels onto the output canvas.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Viewport {
+    pub width: u32,
+    pub height: u32,
+    zoom: f32,
+    offset_x: f32,
+    offset_y: f32,
+}
+
+impl Viewport {
+    pub fn fit(
+        content_width: u32,
+        content_height: u32,
+        options: &ViewportOptions,
+    ) -> io::Result<Self> {
+        let size_error = |size: String| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "Frames would be {} pixels, but must be between 1x1 and {max}x{max}; \
+                     change --zoom, --resolution or --padding",
+                    size,
+                    max = MAX_CANVAS_SIZE
+                ),
+            )
+        };
+        let too_large = || size_error(format!("larger than {0}x{0}", MAX_CANVAS_SIZE));
+        let padding = options.padding.checked_mul(2).ok_or_else(too_large)?;
+        let integer_zoom = |zoom: f32| {
+            let zoom = if options.integer_zoom {
+                zoom.floor().max(1.0)
+            } else {
+                zoom
+            };
+            if zoom.is_finite() && zoom > 0.0 {
+                Ok(zoom)
+            } else {
+                Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "Frames would be scaled by {}, which must be a positive number; \
+                         change --zoom, --resolution or --padding",
+                        zoom
+                    ),
+                ))
+            }
+        };
+        let (zoom, width, height) = match options.resolution {
+            Some((width, height)) => {
+                let available = |size: u32| size.saturating_sub(padding) as f32;
+                let zoom = (available(width) / content_width.max(1) as f32)
+                    .min(available(height) / content_height.max(1) as f32);
+                (integer_zoom(zoom)?, width, height)
+            }
+            None => {
+                let zoom = integer_zoom(options.zoom)?;
+                let scaled = |size: u32| {
+                    let scaled = (size as f32 * zoom).ceil();
+                    if scaled < u32::MAX as f32 {
+                        (scaled as u32).checked_add(padding).ok_or_else(too_large)
+                    } else {
+                        Err(too_large())
+                    }
+                };
+                (zoom, scaled(content_width)?, scaled(content_height)?)
+            }
+        };
+        if width == 0 || height == 0 || width > MAX_CANVAS_SIZE || height > MAX_CANVAS_SIZE {
+            return Err(size_error(format!("{}x{}", width, height)));
+        }
+        Ok(Self {
+            width,
+            height,
+            zoom,
+            offset_x: (width as f32 - content_width as f32 * zoom) / 2.0,
+            offset_y: (height as f32 - content_height as f32 * zoom) / 2.0,
+        })
+    }
+
+    pub fn apply<'a>(&self, renderer: &'a mut dyn Renderer) -> ViewportRenderer<'a> {
+        ViewportRenderer {
+            viewport: *self,
+            renderer,
+        }
+    }
+}
+
+pub struct ViewportRenderer<'a> {
+    viewport: Viewport,
+    renderer: &'a mut dyn Renderer,
+}
+
+impl Renderer for ViewportRenderer<'_> {
+    fn clear(&mut self, color: Color) {
+        self.renderer.clear(color);
+    }
+
+    fn fill_rect(&mut self, rect: Rect, color: Color) {
+        let Viewport {
+            zoom,
+            offset_x,
+            offset_y,
+            ..
+        } = self.viewport;
+        self.renderer.fill_rect(
+            Rect::new(
+                rect.x * zoom + offset_x,
+                rect.y * zoom + offset_y,
+                rect.w * zoom,
+                rect.h * zoom,
+            ),
+            color,
+        );
+    }
+
+    fn scaled_text(&mut self, x: f32, y: f32, scale: f32, text: &str, color: Color) {
+        let Viewport {
+            zoom,
+            offset_x,
+            offset_y,
+            ..
+        } = self.viewport;
+        self.renderer.scaled_text(
+            x * zoom + offset_x,
+            y * zoom + offset_y,
+            scale * zoom,
+            text,
+            color,
+        );
+    }
+
+    fn read_pixels(&mut self) -> GameResult<Vec<u8>> {
+        self.renderer.read_pixels()
+    }
+}